@@ -3,7 +3,7 @@
 
 use std::path::PathBuf;
 
-use clap::{ArgAction, Args, Parser};
+use clap::{ArgAction, Args, Parser, Subcommand};
 use tracing::level_filters::LevelFilter;
 use tracing_appender::rolling::Rotation;
 
@@ -24,6 +24,9 @@ impl From<String> for CliLogParametersFileRotation {
 #[derive(Parser)]
 #[command(about, long_about = None, version, author)]
 pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<CliCommands>,
+
     #[command(flatten)]
     pub log_parameters: CliLogParameters,
 
@@ -43,6 +46,40 @@ pub struct Cli {
     pub http_client_timeout_seconds: u64,
 }
 
+#[derive(Subcommand)]
+pub enum CliCommands {
+    #[command(
+        about = "Validate the program its configuration file without connecting to Discord",
+        long_about = None
+    )]
+    Validate,
+    #[command(
+        about = "Manage the application commands the plugins register",
+        long_about = None
+    )]
+    Commands {
+        #[command(subcommand)]
+        command: CliCommandsCommands,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum CliCommandsCommands {
+    #[command(
+        about = "Initialize the plugins without connecting to Discord and list the application commands with their resolved names",
+        long_about = None
+    )]
+    List,
+    #[command(
+        about = "Initialize the plugins and sync their application commands with Discord without connecting to the gateway",
+        long_about = None
+    )]
+    Sync {
+        #[arg(long, help = "Only print the planned changes", long_help = None)]
+        dry_run: bool,
+    },
+}
+
 #[derive(Args)]
 pub struct CliLogParameters {
     #[arg(default_value = "INFO", short = 'l', long = "log-stdout-level", value_name = "LEVEL", help = "The level at which the program should log to stdout", long_help = None)]
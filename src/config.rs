@@ -1,18 +1,21 @@
 /* SPDX-License-Identifier: GPL-3.0-or-later */
 /* Copyright © 2026 Eduard Smet */
 
-use std::{fs, path::Path};
+use std::{collections::HashMap, fs, path::Path};
 
 use indexmap::IndexMap;
 use serde::Deserialize;
 use tracing::{error, info};
 
-use crate::plugins::ConfigPlugin;
+use crate::{discord::ConfigDiscord, plugins::ConfigPlugin};
 
 #[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct Config {
     #[allow(unused)] // Will be used when multi discord bot client support gets added
     pub name: String,
+    #[serde(default)]
+    pub discord: ConfigDiscord,
     pub plugins: IndexMap<String, ConfigPlugin>,
 }
 
@@ -28,14 +31,48 @@ impl Config {
             }
         };
 
-        match serde_yaml_ng::from_slice::<Config>(&file_bytes) {
-            Ok(config) => Ok(config), // TODO: Env var interpolation
+        // NOTE: Unknown keys, permissions and malformed plugin references get rejected while
+        // deserializing, the path to error wrapper adds the config path to the error its position.
+        match serde_path_to_error::deserialize::<_, Config>(
+            serde_yaml_ng::Deserializer::from_slice(&file_bytes),
+        ) {
+            Ok(config) => {
+                // TODO: Env var interpolation
+                config.validate_custom_id_namespaces(file_path)?;
+
+                Ok(config)
+            }
             Err(err) => {
                 error!(
-                    "An error occurred while trying to deserialize the config file YAML to a struct: {err}"
+                    "The config file is invalid, {} at {}: {}",
+                    file_path.display(),
+                    err.path(),
+                    err.inner()
                 );
                 Err(())
             }
         }
     }
+
+    fn validate_custom_id_namespaces(&self, file_path: &Path) -> Result<(), ()> {
+        let mut custom_id_namespaces = HashMap::new();
+
+        for (plugin_uid, plugin) in &self.plugins {
+            let Some(custom_id_namespace) = &plugin.custom_id_namespace else {
+                continue;
+            };
+
+            if let Some(other_plugin_uid) =
+                custom_id_namespaces.insert(custom_id_namespace, plugin_uid)
+            {
+                error!(
+                    "The config file is invalid, {} at plugins.{plugin_uid}.custom_id_namespace: the {custom_id_namespace} namespace is already used by the {other_plugin_uid} plugin",
+                    file_path.display()
+                );
+                return Err(());
+            }
+        }
+
+        Ok(())
+    }
 }
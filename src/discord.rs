@@ -1,7 +1,13 @@
 /* SPDX-License-Identifier: GPL-3.0-or-later */
 /* Copyright © 2026 Eduard Smet */
 
-use std::{collections::HashMap, sync::Arc};
+use std::{
+    collections::HashMap,
+    sync::{Arc, OnceLock},
+    time::Duration,
+};
+
+use serde::Deserialize;
 
 use tokio::{
     sync::{
@@ -16,54 +22,213 @@ use twilight_gateway::{
     CloseFrame, Config, Event, EventType, EventTypeFlags, Intents, MessageSender, Shard, StreamExt,
 };
 use twilight_http::Client;
-use twilight_model::id::{Id, marker::GuildMarker};
+use twilight_model::{
+    gateway::{
+        OpCode,
+        payload::outgoing::{UpdatePresence, update_presence::UpdatePresencePayload},
+    },
+    id::{
+        Id,
+        marker::{GuildMarker, InteractionMarker},
+    },
+};
 
 use crate::{
     SHUTDOWN,
-    plugins::PluginRegistrations,
+    discord::deferrals::InteractionDeferral,
+    plugins::{AvailablePlugin, ConfigPluginCommandsDefer, PluginRegistrations, PluginScopes},
     utils::channels::{DiscordBotClientMessages, RuntimeMessages},
 };
 
-mod events;
-mod interactions;
+pub mod cache;
+mod custom_ids;
+mod deferrals;
+pub mod events;
+mod intents;
+pub mod interactions;
 mod requests;
+mod scopes;
+
+/// `resource_types`: overrides the cache resource types derived from the plugin registrations.
+/// `message_cache_size`: the amount of messages cached per channel.
+/// `stats_interval_seconds`: how often the cache statistics get logged, disabled when omitted.
+#[derive(Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ConfigDiscordCache {
+    #[serde(default, deserialize_with = "cache::deserialize_resource_types")]
+    pub resource_types: Option<ResourceType>,
+    pub message_cache_size: Option<usize>,
+    pub stats_interval_seconds: Option<u64>,
+}
+
+/// What happens when multiple plugins register a command with the same name, rename suffixes
+/// the names with ~N and fail stops the startup.
+#[derive(Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConfigDiscordCommandConflicts {
+    #[default]
+    Rename,
+    Fail,
+}
+
+/// intents: overrides the gateway intents derived from the plugin registrations.
+/// `privileged_intents`: the privileged intents which may be derived from the plugin
+/// registrations, they have to be enabled in the Discord developer portal as well.
+/// `keep_unknown_commands`: keeps application commands no plugin registers instead of deleting
+/// them, for commands managed by other tooling.
+/// `sweep_guild_commands`: deletes the unknown commands of every guild the bot is in, instead
+/// of only the guilds the plugins register commands in.
+/// presence: the presence set when connecting, check the [Update Presence docs] for the
+/// structure.
+///
+/// [Update Presence docs]: https://discord.com/developers/docs/events/gateway-events#update-presence
+#[derive(Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ConfigDiscord {
+    #[serde(default, deserialize_with = "intents::deserialize_intents")]
+    pub intents: Option<Intents>,
+    #[serde(default, deserialize_with = "intents::deserialize_privileged_intents")]
+    pub privileged_intents: Option<Intents>,
+    pub presence: Option<UpdatePresencePayload>,
+    #[serde(default)]
+    pub command_conflicts: ConfigDiscordCommandConflicts,
+    #[serde(default)]
+    pub keep_unknown_commands: bool,
+    #[serde(default)]
+    pub sweep_guild_commands: bool,
+    #[serde(default)]
+    pub cache: ConfigDiscordCache,
+}
+
+/// The gateway connection, only created after the plugin initialization as the intents and
+/// cache resource types get derived from the registrations.
+struct DiscordGateway {
+    shard_message_senders: HashMap<u32, Arc<MessageSender>>, // Shard ID, message sender
+    shard_count: u32,
+    cache: Arc<InMemoryCache>,
+}
 
 pub struct DiscordBotClient {
     http_client: Arc<Client>,
-    shard_message_senders: Arc<RwLock<HashMap<Id<GuildMarker>, Arc<MessageSender>>>>,
-    cache: Arc<InMemoryCache>,
+    gateway: OnceLock<DiscordGateway>,
+    shard_tasks: Mutex<Vec<JoinHandle<()>>>,
+    guild_shards: Arc<RwLock<HashMap<Id<GuildMarker>, u32>>>, // Guild ID, shard ID
+    presence: RwLock<Option<UpdatePresencePayload>>,
+    cache_stats_interval: Option<Duration>,
+    cache_stats_reporter: Mutex<Option<JoinHandle<()>>>,
     plugin_registrations: Arc<RwLock<PluginRegistrations>>,
+    plugin_scopes: HashMap<String, PluginScopes>,
+    custom_id_namespaces: HashMap<String, String>, // Namespace, plugin ID
+    keep_unknown_commands: bool,
+    sweep_guild_commands: bool,
+    command_deferrals: HashMap<String, HashMap<String, ConfigPluginCommandsDefer>>, // Plugin ID, (requested name, deferral)
+    interaction_deferrals: Mutex<HashMap<Id<InteractionMarker>, Arc<InteractionDeferral>>>,
     runtime_tx: Arc<Sender<RuntimeMessages>>,
     runtime_rx: Arc<Mutex<Receiver<DiscordBotClientMessages>>>,
 }
 
 impl DiscordBotClient {
-    pub async fn new(
+    pub fn new(
         token: String,
+        config_discord: ConfigDiscord,
+        available_plugins: &HashMap<String, AvailablePlugin>,
         plugin_registrations: Arc<RwLock<PluginRegistrations>>,
         runtime_tx: Sender<RuntimeMessages>,
         runtime_rx: Receiver<DiscordBotClientMessages>,
-    ) -> Result<(Self, Box<dyn ExactSizeIterator<Item = Shard> + Send>), ()> {
+    ) -> Self {
         info!("Creating the Discord bot client");
 
-        let intents = Intents::all();
+        let plugin_scopes = PluginScopes::new(available_plugins);
+
+        let custom_id_namespaces = available_plugins
+            .iter()
+            .filter_map(|(plugin_uid, available_plugin)| {
+                available_plugin
+                    .custom_id_namespace
+                    .clone()
+                    .map(|custom_id_namespace| (custom_id_namespace, plugin_uid.clone()))
+            })
+            .collect();
+
+        let command_deferrals = available_plugins
+            .iter()
+            .filter_map(|(plugin_uid, available_plugin)| {
+                available_plugin
+                    .commands
+                    .as_ref()
+                    .filter(|commands| !commands.defer.is_empty())
+                    .map(|commands| (plugin_uid.clone(), commands.defer.clone()))
+            })
+            .collect();
 
         rustls::crypto::aws_lc_rs::default_provider()
             .install_default()
             .unwrap();
 
-        let http_client = Client::new(token.clone());
+        DiscordBotClient {
+            http_client: Arc::new(Client::new(token)),
+            gateway: OnceLock::new(),
+            shard_tasks: Mutex::new(vec![]),
+            guild_shards: Arc::new(RwLock::new(HashMap::new())),
+            presence: RwLock::new(config_discord.presence),
+            cache_stats_interval: config_discord
+                .cache
+                .stats_interval_seconds
+                .map(Duration::from_secs),
+            cache_stats_reporter: Mutex::new(None),
+            plugin_registrations,
+            plugin_scopes,
+            custom_id_namespaces,
+            keep_unknown_commands: config_discord.keep_unknown_commands,
+            sweep_guild_commands: config_discord.sweep_guild_commands,
+            command_deferrals,
+            interaction_deferrals: Mutex::new(HashMap::new()),
+            runtime_tx: Arc::new(runtime_tx),
+            runtime_rx: Arc::new(Mutex::new(runtime_rx)),
+        }
+    }
+
+    /// Connects to the gateway with the intents and cache resource types derived from the
+    /// events the plugins registered, so it has to be called after the plugin initialization.
+    pub async fn connect(
+        discord_bot_client: Arc<DiscordBotClient>,
+        token: String,
+        config_discord: &ConfigDiscord,
+        available_plugins: &HashMap<String, AvailablePlugin>,
+    ) -> Result<(), ()> {
+        info!("Connecting to the Discord gateway");
+
+        let registered_events = discord_bot_client
+            .plugin_registrations
+            .read()
+            .await
+            .discord_events
+            .events
+            .clone();
+
+        let intents = intents::intents(
+            config_discord.intents,
+            config_discord.privileged_intents,
+            &registered_events,
+        );
+
+        let mut config_builder = Config::builder(token, intents);
+
+        // Plugins could have changed the presence during their initialization
+        if let Some(presence) = discord_bot_client.presence.read().await.clone() {
+            config_builder = config_builder.presence(presence);
+        }
 
-        let config = Config::new(token, intents);
+        let config = config_builder.build();
 
         let shards = match twilight_gateway::create_recommended(
-            &http_client,
+            &discord_bot_client.http_client,
             config,
             |_, builder| builder.build(),
         )
         .await
         {
-            Ok(shards) => Box::new(shards),
+            Ok(shards) => shards.collect::<Vec<Shard>>(),
             Err(err) => {
                 error!(
                     "Something went wrong while getting the recommended amount of shards from Discord, error: {}",
@@ -73,39 +238,62 @@ impl DiscordBotClient {
             }
         };
 
-        let shard_message_senders = Arc::new(RwLock::new(HashMap::new()));
+        let shard_count = shards.first().map_or(1, |shard| shard.id().total());
 
-        let cache = Arc::new(
-            DefaultInMemoryCache::builder()
-                .resource_types(ResourceType::all())
-                .build(),
-        );
+        let shard_message_senders = shards
+            .iter()
+            .map(|shard| (shard.id().number(), Arc::new(shard.sender())))
+            .collect();
 
-        Ok((
-            DiscordBotClient {
-                http_client: Arc::new(http_client),
-                shard_message_senders,
-                cache,
-                plugin_registrations,
-                runtime_tx: Arc::new(runtime_tx),
-                runtime_rx: Arc::new(Mutex::new(runtime_rx)),
-            },
-            shards,
-        ))
-    }
+        let resource_types =
+            cache::resource_types(&config_discord.cache, available_plugins, &registered_events);
 
-    pub fn start(self, shards: Box<dyn ExactSizeIterator<Item = Shard> + Send>) -> JoinHandle<()> {
-        let mut tasks = Vec::with_capacity(shards.len());
+        info!("Caching the following Discord resource types: {resource_types:?}");
 
-        let discord_bot_client = Arc::new(self);
+        let mut cache_builder = DefaultInMemoryCache::builder().resource_types(resource_types);
+
+        if let Some(message_cache_size) = config_discord.cache.message_cache_size {
+            cache_builder = cache_builder.message_cache_size(message_cache_size);
+        }
+
+        if discord_bot_client
+            .gateway
+            .set(DiscordGateway {
+                shard_message_senders,
+                shard_count,
+                cache: Arc::new(cache_builder.build()),
+            })
+            .is_err()
+        {
+            error!("The Discord bot client is already connected to the gateway");
+            return Err(());
+        }
+
+        let mut shard_tasks = discord_bot_client.shard_tasks.lock().await;
 
         for shard in shards {
-            tasks.push(tokio::spawn(Self::shard_runner(
+            shard_tasks.push(tokio::spawn(Self::shard_runner(
                 discord_bot_client.clone(),
                 shard,
             )));
         }
 
+        if let Some(interval) = discord_bot_client.cache_stats_interval {
+            let discord_bot_client_clone = discord_bot_client.clone();
+
+            *discord_bot_client.cache_stats_reporter.lock().await =
+                Some(tokio::spawn(async move {
+                    discord_bot_client_clone
+                        .cache_stats_reporter(interval)
+                        .await;
+                }));
+        }
+
+        Ok(())
+    }
+
+    /// Handles the runtime messages, the REST requests work before the gateway is connected.
+    pub fn start(discord_bot_client: Arc<DiscordBotClient>) -> JoinHandle<()> {
         tokio::spawn(async move {
             while let Some(message) = discord_bot_client.runtime_rx.lock().await.recv().await {
                 match message {
@@ -114,20 +302,69 @@ impl DiscordBotClient {
                             .application_command_registrations(commands)
                             .await;
                     }
-                    DiscordBotClientMessages::Request(request, response_sender) => {
+                    DiscordBotClientMessages::Request(plugin_uid, mut request, response_sender) => {
+                        if !discord_bot_client.plugin_scope_allows_request(&plugin_uid, &request) {
+                            let _ = response_sender.send(Err(format!(
+                                "The {plugin_uid} plugin is not allowed to address the guild or channel of this {} request",
+                                request.name()
+                            )
+                            .into()));
+                            continue;
+                        }
+
+                        if let Err(err) =
+                            discord_bot_client.namespace_custom_ids(&plugin_uid, &mut request)
+                        {
+                            let _ = response_sender.send(Err(err.into()));
+                            continue;
+                        }
+
+                        let request = match discord_bot_client
+                            .deferred_interaction_request(request)
+                            .await
+                        {
+                            Ok(Some(request)) => request,
+                            Ok(None) => {
+                                let _ = response_sender.send(Ok(None));
+                                continue;
+                            }
+                            Err(err) => {
+                                let _ = response_sender.send(Err(err.into()));
+                                continue;
+                            }
+                        };
+
                         let _ = response_sender.send(discord_bot_client.request(request).await);
                     }
+                    DiscordBotClientMessages::CacheRequest(
+                        plugin_uid,
+                        request,
+                        response_sender,
+                    ) => {
+                        let _ = response_sender
+                            .send(discord_bot_client.cache_request(&plugin_uid, request));
+                    }
                     DiscordBotClientMessages::Shutdown(is_done) => {
-                        for sender in discord_bot_client
-                            .shard_message_senders
-                            .read()
-                            .await
-                            .values()
+                        if let Some(gateway) = discord_bot_client.gateway.get() {
+                            for sender in gateway.shard_message_senders.values() {
+                                _ = sender.close(CloseFrame::NORMAL);
+                            }
+                        }
+
+                        if let Some(cache_stats_reporter) =
+                            discord_bot_client.cache_stats_reporter.lock().await.take()
                         {
-                            _ = sender.close(CloseFrame::NORMAL);
+                            cache_stats_reporter.abort();
                         }
 
-                        for task in tasks.drain(..) {
+                        let shard_tasks = discord_bot_client
+                            .shard_tasks
+                            .lock()
+                            .await
+                            .drain(..)
+                            .collect::<Vec<_>>();
+
+                        for task in shard_tasks {
                             let _ = task.await;
                         }
 
@@ -139,7 +376,7 @@ impl DiscordBotClient {
     }
 
     pub async fn shard_runner(discord_bot_client: Arc<DiscordBotClient>, mut shard: Shard) {
-        let shard_message_sender = Arc::new(shard.sender());
+        let shard_id = shard.id().number();
 
         while let Some(item) = shard.next_event(EventTypeFlags::all()).await {
             let Ok(event) = item else {
@@ -155,24 +392,74 @@ impl DiscordBotClient {
                 break;
             }
 
-            discord_bot_client.cache.update(&event);
+            if let Some(gateway) = discord_bot_client.gateway.get() {
+                gateway.cache.update(&event);
+            }
 
             match event {
                 Event::Ready(ready) => {
                     info!("Shard is ready, logged in as {}", &ready.user.name);
 
+                    // A new session starts with the presence from the config, reapply the last
+                    // presence in case a plugin changed it
+                    if let Some(presence) = discord_bot_client.presence.read().await.clone()
+                        && let Some(gateway) = discord_bot_client.gateway.get()
+                        && let Some(shard_message_sender) =
+                            gateway.shard_message_senders.get(&shard_id)
+                    {
+                        let _ = shard_message_sender.command(&UpdatePresence {
+                            d: presence,
+                            op: OpCode::PresenceUpdate,
+                        });
+                    }
+
+                    let mut guild_shards = discord_bot_client.guild_shards.write().await;
+
                     for guild in ready.guilds {
-                        discord_bot_client
-                            .shard_message_senders
-                            .write()
-                            .await
-                            .insert(guild.id, shard_message_sender.clone());
+                        guild_shards.insert(guild.id, shard_id);
                     }
                 }
-                _ => {
+                event => {
+                    match &event {
+                        Event::GuildCreate(guild_create) => {
+                            discord_bot_client
+                                .guild_shards
+                                .write()
+                                .await
+                                .insert(guild_create.id(), shard_id);
+                        }
+                        // Unavailable guilds are outages, the bot is still in them
+                        Event::GuildDelete(guild_delete)
+                            if guild_delete.unavailable != Some(true) =>
+                        {
+                            discord_bot_client
+                                .guild_shards
+                                .write()
+                                .await
+                                .remove(&guild_delete.id);
+                        }
+                        _ => {}
+                    }
+
                     tokio::spawn(Self::handle_event(discord_bot_client.clone(), event));
                 }
             }
         }
     }
+
+    /// The message sender of the shard a guild is on, guilds which are not known yet get
+    /// routed with Discord its sharding formula.
+    pub async fn guild_shard_message_sender(
+        &self,
+        guild_id: Id<GuildMarker>,
+    ) -> Option<Arc<MessageSender>> {
+        let gateway = self.gateway.get()?;
+
+        let shard_id = match self.guild_shards.read().await.get(&guild_id) {
+            Some(shard_id) => *shard_id,
+            None => u32::try_from((guild_id.get() >> 22) % u64::from(gateway.shard_count)).ok()?,
+        };
+
+        gateway.shard_message_senders.get(&shard_id).cloned()
+    }
 }
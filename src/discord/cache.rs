@@ -0,0 +1,299 @@
+/* SPDX-License-Identifier: GPL-3.0-or-later */
+/* Copyright © 2026 Eduard Smet */
+
+use std::{collections::HashMap, fmt, time::Duration};
+
+use serde::{
+    Deserializer, Serialize,
+    de::{self, SeqAccess, Visitor},
+};
+use tracing::info;
+use twilight_cache_inmemory::ResourceType;
+use twilight_gateway::EventType;
+use twilight_model::id::Id;
+
+use crate::{
+    discord::{ConfigDiscordCache, DiscordBotClient},
+    plugins::AvailablePlugin,
+};
+
+/// The resource types the discord-cache interface reads from.
+const CACHE_INTERFACE_RESOURCE_TYPES: ResourceType = ResourceType::GUILD
+    .union(ResourceType::CHANNEL)
+    .union(ResourceType::MEMBER)
+    .union(ResourceType::ROLE)
+    .union(ResourceType::USER)
+    .union(ResourceType::USER_CURRENT);
+
+/// The resource types needed to cache the data of an event.
+fn event_resource_types(event_type: EventType) -> ResourceType {
+    match event_type {
+        EventType::MessageCreate
+        | EventType::MessageDelete
+        | EventType::MessageDeleteBulk
+        | EventType::MessageUpdate => ResourceType::MESSAGE,
+        EventType::ReactionAdd
+        | EventType::ReactionRemove
+        | EventType::ReactionRemoveAll
+        | EventType::ReactionRemoveEmoji => ResourceType::MESSAGE | ResourceType::REACTION,
+        EventType::PresenceUpdate => ResourceType::PRESENCE,
+        EventType::VoiceStateUpdate => ResourceType::VOICE_STATE,
+        _ => ResourceType::empty(),
+    }
+}
+
+/// The channels are always cached as the plugin scopes resolve the guild and parent of a
+/// channel through them. The other resource types only get cached when a plugin can read
+/// them through the discord-cache interface, the event data only for the events such
+/// plugins registered, unless the config overrides them.
+pub fn resource_types(
+    config_discord_cache: &ConfigDiscordCache,
+    available_plugins: &HashMap<String, AvailablePlugin>,
+    registered_events: &HashMap<EventType, Vec<String>>,
+) -> ResourceType {
+    if let Some(resource_types) = config_discord_cache.resource_types {
+        return resource_types | ResourceType::CHANNEL;
+    }
+
+    let reads_cache = |plugin_uid: &String| {
+        available_plugins
+            .get(plugin_uid)
+            .is_some_and(|available_plugin| available_plugin.permissions.discord_cache)
+    };
+
+    let mut resource_types = ResourceType::CHANNEL;
+
+    if available_plugins.keys().any(reads_cache) {
+        resource_types |= CACHE_INTERFACE_RESOURCE_TYPES;
+    }
+
+    for (event_type, plugin_uids) in registered_events {
+        if plugin_uids.iter().any(reads_cache) {
+            resource_types |= event_resource_types(*event_type);
+        }
+    }
+
+    resource_types
+}
+
+/// Deserializes a list of twilight cache resource type names (like GUILD or `voice_state`).
+pub fn deserialize_resource_types<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<ResourceType>, D::Error> {
+    struct ResourceTypesVisitor;
+
+    impl<'de> Visitor<'de> for ResourceTypesVisitor {
+        type Value = ResourceType;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            formatter.write_str("a list of cache resource types")
+        }
+
+        fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+            let mut resource_types = ResourceType::empty();
+
+            while let Some(name) = seq.next_element::<String>()? {
+                let Some(resource_type) = ResourceType::from_name(&name.to_uppercase()) else {
+                    let names = ResourceType::all()
+                        .iter_names()
+                        .map(|(name, _)| name)
+                        .collect::<Vec<_>>();
+
+                    return Err(de::Error::custom(format!(
+                        "unknown resource type `{name}`, expected one of {}",
+                        names.join(", ")
+                    )));
+                };
+
+                resource_types |= resource_type;
+            }
+
+            Ok(resource_types)
+        }
+    }
+
+    deserializer.deserialize_seq(ResourceTypesVisitor).map(Some)
+}
+
+/// The lookups of the discord-cache plugin interface, the IDs are snowflakes.
+pub enum DiscordCacheRequests {
+    Guild(u64),
+    Channel(u64),
+    Member(u64, u64),
+    Role(u64),
+    User(u64),
+    VoiceState(u64, u64),
+    Message(u64),
+    GuildMembers(u64),
+    GuildChannels(u64),
+}
+
+impl DiscordBotClient {
+    /// Looks up a resource in the cache and serializes it to JSON, resources outside of the
+    /// plugin scopes are treated as not cached.
+    pub fn cache_request(
+        &self,
+        plugin_uid: &str,
+        request: DiscordCacheRequests,
+    ) -> Result<Option<Vec<u8>>, String> {
+        let Some(gateway) = self.gateway.get() else {
+            return Err(String::from("Not connected to the Discord gateway yet"));
+        };
+
+        let cache = &gateway.cache;
+
+        match request {
+            DiscordCacheRequests::Guild(guild_id) => {
+                let guild_id = Self::cache_id(guild_id)?;
+
+                if !self.plugin_scope_allows(plugin_uid, Some(guild_id), None) {
+                    return Ok(None);
+                }
+
+                Self::serialize_cached(cache.guild(guild_id).as_deref())
+            }
+            DiscordCacheRequests::Channel(channel_id) => {
+                let channel_id = Self::cache_id(channel_id)?;
+
+                if !self.plugin_scope_allows(plugin_uid, None, Some(channel_id)) {
+                    return Ok(None);
+                }
+
+                Self::serialize_cached(cache.channel(channel_id).as_deref())
+            }
+            DiscordCacheRequests::Member(guild_id, user_id) => {
+                let guild_id = Self::cache_id(guild_id)?;
+
+                if !self.plugin_scope_allows(plugin_uid, Some(guild_id), None) {
+                    return Ok(None);
+                }
+
+                Self::serialize_cached(cache.member(guild_id, Self::cache_id(user_id)?).as_deref())
+            }
+            DiscordCacheRequests::Role(role_id) => {
+                let Some(role) = cache.role(Self::cache_id(role_id)?) else {
+                    return Ok(None);
+                };
+
+                if !self.plugin_scope_allows(plugin_uid, Some(role.guild_id()), None) {
+                    return Ok(None);
+                }
+
+                Self::serialize_cached(Some(role.resource()))
+            }
+            DiscordCacheRequests::User(user_id) => {
+                Self::serialize_cached(cache.user(Self::cache_id(user_id)?).as_deref())
+            }
+            DiscordCacheRequests::VoiceState(guild_id, user_id) => {
+                let guild_id = Self::cache_id(guild_id)?;
+
+                if !self.plugin_scope_allows(plugin_uid, Some(guild_id), None) {
+                    return Ok(None);
+                }
+
+                Self::serialize_cached(
+                    cache
+                        .voice_state(Self::cache_id(user_id)?, guild_id)
+                        .as_deref(),
+                )
+            }
+            DiscordCacheRequests::Message(message_id) => {
+                let Some(message) = cache.message(Self::cache_id(message_id)?) else {
+                    return Ok(None);
+                };
+
+                if !self.plugin_scope_allows(plugin_uid, None, Some(message.channel_id())) {
+                    return Ok(None);
+                }
+
+                Self::serialize_cached(Some(message.value()))
+            }
+            DiscordCacheRequests::GuildMembers(guild_id) => {
+                let guild_id = Self::cache_id(guild_id)?;
+
+                if !self.plugin_scope_allows(plugin_uid, Some(guild_id), None) {
+                    return Ok(None);
+                }
+
+                let Some(user_ids) = cache.guild_members(guild_id) else {
+                    return Ok(None);
+                };
+
+                let members = user_ids
+                    .iter()
+                    .filter_map(|user_id| cache.member(guild_id, *user_id))
+                    .map(|member| member.value().clone())
+                    .collect::<Vec<_>>();
+
+                Self::serialize_cached(Some(&members))
+            }
+            DiscordCacheRequests::GuildChannels(guild_id) => {
+                let guild_id = Self::cache_id(guild_id)?;
+
+                if !self.plugin_scope_allows(plugin_uid, Some(guild_id), None) {
+                    return Ok(None);
+                }
+
+                let Some(channel_ids) = cache.guild_channels(guild_id) else {
+                    return Ok(None);
+                };
+
+                let channels = channel_ids
+                    .iter()
+                    .filter(|channel_id| {
+                        self.plugin_scope_allows(plugin_uid, Some(guild_id), Some(**channel_id))
+                    })
+                    .filter_map(|channel_id| cache.channel(*channel_id))
+                    .map(|channel| channel.value().clone())
+                    .collect::<Vec<_>>();
+
+                Self::serialize_cached(Some(&channels))
+            }
+        }
+    }
+
+    fn cache_id<T>(id: u64) -> Result<Id<T>, String> {
+        Id::new_checked(id).ok_or_else(|| String::from("IDs can not be zero"))
+    }
+
+    fn serialize_cached<T: Serialize + ?Sized>(
+        value: Option<&T>,
+    ) -> Result<Option<Vec<u8>>, String> {
+        match value.map(sonic_rs::to_vec).transpose() {
+            Ok(bytes) => Ok(bytes),
+            Err(err) => Err(format!(
+                "Something went wrong while serializing a cached resource, error: {err}"
+            )),
+        }
+    }
+
+    /// Logs the amount of cached resources every interval, twilight does not track the memory
+    /// usage itself so the counts are the closest estimate.
+    pub async fn cache_stats_reporter(&self, interval: Duration) {
+        let Some(gateway) = self.gateway.get() else {
+            return;
+        };
+
+        let mut interval = tokio::time::interval(interval);
+
+        interval.tick().await;
+
+        loop {
+            interval.tick().await;
+
+            let stats = gateway.cache.stats();
+
+            info!(
+                "Discord cache statistics: {} guilds, {} channels, {} members, {} users, {} roles, {} voice states, {} presences, {} emojis",
+                stats.guilds(),
+                stats.channels(),
+                stats.members(),
+                stats.users(),
+                stats.roles(),
+                stats.voice_states(),
+                stats.presences(),
+                stats.emojis()
+            );
+        }
+    }
+}
@@ -0,0 +1,268 @@
+/* SPDX-License-Identifier: GPL-3.0-or-later */
+/* Copyright © 2026 Eduard Smet */
+
+use std::borrow::Cow;
+
+use sonic_rs::{JsonValueMutTrait, JsonValueTrait, Value as JsonValue, pointer};
+use twilight_model::{
+    application::interaction::InteractionData, gateway::payload::incoming::InteractionCreate,
+};
+
+use crate::{
+    discord::DiscordBotClient,
+    plugins::discord_bot::plugin::{discord_types::Contents, host_functions::DiscordRequests},
+};
+
+/// The maximum length of a custom ID, including the namespace prefix.
+const CUSTOM_ID_MAX_LENGTH: usize = 100;
+
+impl DiscordBotClient {
+    /// Prefixes the custom IDs in the body of a request with the namespace of the plugin, when
+    /// it opted into custom ID namespacing.
+    pub fn namespace_custom_ids(
+        &self,
+        plugin_uid: &str,
+        request: &mut DiscordRequests,
+    ) -> Result<(), String> {
+        let Some(namespace) =
+            self.custom_id_namespaces
+                .iter()
+                .find_map(|(namespace, namespace_plugin_uid)| {
+                    (namespace_plugin_uid == plugin_uid).then_some(namespace)
+                })
+        else {
+            return Ok(());
+        };
+
+        match request {
+            DiscordRequests::CreateForumThread((_, contents))
+            | DiscordRequests::CreateMessage((_, contents))
+            | DiscordRequests::CreateFollowup((_, _, contents))
+            | DiscordRequests::UpdateFollowup((_, _, _, contents))
+            | DiscordRequests::UpdateInteractionOriginal((_, _, contents))
+            | DiscordRequests::RawHttp((_, _, Some(contents))) => match contents {
+                Contents::Json(body) => *body = namespace_json(namespace, body)?,
+                Contents::Form(buffer) => *buffer = namespace_form(namespace, buffer)?,
+            },
+            DiscordRequests::InteractionCallback((_, _, _, body)) => {
+                *body = namespace_json(namespace, body)?;
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    /// The plugin owning the namespace of a component or modal interaction its custom ID,
+    /// together with the interaction with the namespace stripped from the custom ID.
+    pub fn strip_custom_id_namespace<'a>(
+        &self,
+        interaction_create: &'a InteractionCreate,
+    ) -> Option<(String, Cow<'a, InteractionCreate>)> {
+        let custom_id = match interaction_create.data.as_ref()? {
+            InteractionData::MessageComponent(message_component_interaction_data) => {
+                &message_component_interaction_data.custom_id
+            }
+            InteractionData::ModalSubmit(modal_interaction_data) => {
+                &modal_interaction_data.custom_id
+            }
+            _ => return None,
+        };
+
+        let (namespace, stripped_custom_id) = custom_id.split_once(':')?;
+        let plugin_uid = self.custom_id_namespaces.get(namespace)?;
+
+        let mut interaction_create = interaction_create.clone();
+
+        match interaction_create.0.data.as_mut() {
+            Some(InteractionData::MessageComponent(message_component_interaction_data)) => {
+                message_component_interaction_data.custom_id = stripped_custom_id.to_string();
+            }
+            Some(InteractionData::ModalSubmit(modal_interaction_data)) => {
+                modal_interaction_data.custom_id = stripped_custom_id.to_string();
+            }
+            _ => {}
+        }
+
+        Some((plugin_uid.clone(), Cow::Owned(interaction_create)))
+    }
+}
+
+/// Modal interaction callbacks only get the custom ID of the modal namespaced, the custom
+/// IDs of its text inputs are returned as is in the modal submit interaction.
+fn namespace_json(namespace: &str, body: &[u8]) -> Result<Vec<u8>, String> {
+    let mut value = match sonic_rs::from_slice::<JsonValue>(body) {
+        Ok(value) => value,
+        Err(err) => {
+            return Err(format!(
+                "Something went wrong while deserializing the request body to namespace its custom IDs, error: {err}"
+            ));
+        }
+    };
+
+    if value.get("type").and_then(JsonValue::as_u64) == Some(9) {
+        if let Some(custom_id) = value.pointer_mut(&pointer!["data", "custom_id"]) {
+            prefix_custom_id(namespace, custom_id)?;
+        }
+    } else {
+        prefix_custom_ids(namespace, &mut value)?;
+    }
+
+    match sonic_rs::to_vec(&value) {
+        Ok(body) => Ok(body),
+        Err(err) => Err(format!(
+            "Something went wrong while serializing the namespaced request body, error: {err}"
+        )),
+    }
+}
+
+/// Namespaces the `payload_json` part of a multipart form, the other parts are files.
+fn namespace_form(namespace: &str, buffer: &[u8]) -> Result<Vec<u8>, String> {
+    let Some(header_start) = find(buffer, b"name=\"payload_json\"") else {
+        return Ok(buffer.to_vec());
+    };
+
+    let Some(payload_start) =
+        find(&buffer[header_start..], b"\r\n\r\n").map(|position| header_start + position + 4)
+    else {
+        return Err(String::from("The payload_json form part has no body"));
+    };
+
+    let Some(payload_end) =
+        find(&buffer[payload_start..], b"\r\n--").map(|position| payload_start + position)
+    else {
+        return Err(String::from("The payload_json form part is not terminated"));
+    };
+
+    let payload = namespace_json(namespace, &buffer[payload_start..payload_end])?;
+
+    let mut namespaced_buffer =
+        Vec::with_capacity(buffer.len() - (payload_end - payload_start) + payload.len());
+
+    namespaced_buffer.extend_from_slice(&buffer[..payload_start]);
+    namespaced_buffer.extend_from_slice(&payload);
+    namespaced_buffer.extend_from_slice(&buffer[payload_end..]);
+
+    Ok(namespaced_buffer)
+}
+
+fn prefix_custom_ids(namespace: &str, value: &mut JsonValue) -> Result<(), String> {
+    if let Some(object) = value.as_object_mut() {
+        for (key, value) in object.iter_mut() {
+            if key == "custom_id" {
+                prefix_custom_id(namespace, value)?;
+            } else {
+                prefix_custom_ids(namespace, value)?;
+            }
+        }
+    } else if let Some(array) = value.as_array_mut() {
+        for value in array.iter_mut() {
+            prefix_custom_ids(namespace, value)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Custom IDs which already carry the namespace are left as is, components copied from a
+/// received message keep their namespace and would otherwise get prefixed twice.
+fn prefix_custom_id(namespace: &str, value: &mut JsonValue) -> Result<(), String> {
+    if let Some(custom_id) = value.as_str()
+        && !custom_id
+            .strip_prefix(namespace)
+            .is_some_and(|custom_id| custom_id.starts_with(':'))
+    {
+        let namespaced_custom_id = format!("{namespace}:{custom_id}");
+
+        if namespaced_custom_id.chars().count() > CUSTOM_ID_MAX_LENGTH {
+            return Err(format!(
+                "The {custom_id} custom ID exceeds the {CUSTOM_ID_MAX_LENGTH} character limit of Discord once prefixed with the {namespace} namespace, it can be at most {} characters long",
+                CUSTOM_ID_MAX_LENGTH - namespace.chars().count() - 1
+            ));
+        }
+
+        *value = JsonValue::from(namespaced_custom_id.as_str());
+    }
+
+    Ok(())
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn namespace_json_prefixes_nested_custom_ids() {
+        let body = br#"{"components":[{"type":1,"components":[{"type":2,"custom_id":"vote"}]}]}"#;
+
+        let namespaced_body = namespace_json("poll", body).unwrap();
+
+        assert_eq!(
+            sonic_rs::from_slice::<JsonValue>(&namespaced_body).unwrap(),
+            sonic_rs::json!({"components":[{"type":1,"components":[{"type":2,"custom_id":"poll:vote"}]}]})
+        );
+    }
+
+    #[test]
+    fn namespace_json_does_not_prefix_twice() {
+        let body = br#"{"components":[{"type":1,"components":[{"type":2,"custom_id":"vote"}]}]}"#;
+
+        let namespaced_body = namespace_json("poll", body).unwrap();
+
+        // A plugin re-sending the components of a received message, which keep their namespace
+        assert_eq!(
+            namespace_json("poll", &namespaced_body).unwrap(),
+            namespaced_body
+        );
+        assert_eq!(
+            sonic_rs::from_slice::<JsonValue>(&namespace_json("other", &namespaced_body).unwrap())
+                .unwrap()
+                .pointer(&pointer!["components", 0, "components", 0, "custom_id"])
+                .and_then(JsonValue::as_str),
+            Some("other:poll:vote")
+        );
+    }
+
+    #[test]
+    fn namespace_json_only_prefixes_the_modal_custom_id() {
+        let body = br#"{"type":9,"data":{"custom_id":"form","components":[{"type":1,"components":[{"type":4,"custom_id":"name"}]}]}}"#;
+
+        let value =
+            sonic_rs::from_slice::<JsonValue>(&namespace_json("poll", body).unwrap()).unwrap();
+
+        assert_eq!(
+            value
+                .pointer(&pointer!["data", "custom_id"])
+                .and_then(JsonValue::as_str),
+            Some("poll:form")
+        );
+        assert_eq!(
+            value
+                .pointer(&pointer![
+                    "data",
+                    "components",
+                    0,
+                    "components",
+                    0,
+                    "custom_id"
+                ])
+                .and_then(JsonValue::as_str),
+            Some("name")
+        );
+    }
+
+    #[test]
+    fn prefix_custom_id_enforces_the_length_limit() {
+        let mut value = JsonValue::from("a".repeat(95).as_str());
+        assert!(prefix_custom_id("poll", &mut value).is_ok());
+
+        let mut value = JsonValue::from("a".repeat(96).as_str());
+        assert!(prefix_custom_id("poll", &mut value).is_err());
+    }
+}
@@ -0,0 +1,243 @@
+/* SPDX-License-Identifier: GPL-3.0-or-later */
+/* Copyright © 2026 Eduard Smet */
+
+use std::{sync::Arc, time::Duration};
+
+use sonic_rs::{JsonValueMutTrait, JsonValueTrait, Value as JsonValue};
+use tokio::sync::Mutex;
+use tracing::error;
+use twilight_http::{request::Request, routing::Route};
+use twilight_model::{
+    application::interaction::Interaction,
+    channel::message::MessageFlags,
+    http::interaction::{InteractionResponse, InteractionResponseData, InteractionResponseType},
+    id::{
+        Id,
+        marker::{ApplicationMarker, InteractionMarker},
+    },
+};
+
+use crate::{
+    discord::DiscordBotClient,
+    plugins::{
+        ConfigPluginCommandsDefer,
+        discord_bot::plugin::{discord_types::Contents, host_functions::DiscordRequests},
+    },
+};
+
+const DEFAULT_DEFER_THRESHOLD: Duration = Duration::from_millis(2000);
+
+const INTERACTION_TOKEN_LIFETIME: Duration = Duration::from_secs(15 * 60);
+
+#[derive(PartialEq)]
+enum InteractionDeferralState {
+    Pending,
+    Deferred,
+    Responded,
+}
+
+pub struct InteractionDeferral {
+    application_id: Id<ApplicationMarker>,
+    state: Mutex<InteractionDeferralState>,
+}
+
+impl DiscordBotClient {
+    /// Defers the interaction when the plugin has not responded to it within the threshold.
+    pub async fn schedule_interaction_deferral(
+        discord_bot_client: Arc<DiscordBotClient>,
+        interaction: &Interaction,
+        defer: &ConfigPluginCommandsDefer,
+    ) {
+        let interaction_id = interaction.id;
+        let interaction_token = interaction.token.clone();
+        let ephemeral = defer.ephemeral;
+        let threshold = defer
+            .threshold_ms
+            .map_or(DEFAULT_DEFER_THRESHOLD, Duration::from_millis);
+
+        discord_bot_client
+            .interaction_deferrals
+            .lock()
+            .await
+            .insert(
+                interaction_id,
+                Arc::new(InteractionDeferral {
+                    application_id: interaction.application_id,
+                    state: Mutex::new(InteractionDeferralState::Pending),
+                }),
+            );
+
+        tokio::spawn(async move {
+            tokio::time::sleep(threshold).await;
+
+            let Some(interaction_deferral) = discord_bot_client
+                .interaction_deferrals
+                .lock()
+                .await
+                .get(&interaction_id)
+                .cloned()
+            else {
+                return;
+            };
+
+            {
+                let mut state = interaction_deferral.state.lock().await;
+
+                if *state != InteractionDeferralState::Pending {
+                    return;
+                }
+
+                if discord_bot_client
+                    .defer_interaction(interaction_id, &interaction_token, ephemeral)
+                    .await
+                    .is_ok()
+                {
+                    *state = InteractionDeferralState::Deferred;
+                } else {
+                    *state = InteractionDeferralState::Responded;
+                }
+            }
+
+            // Forget interactions the plugin never responded to once their token expired
+            tokio::time::sleep(INTERACTION_TOKEN_LIFETIME.saturating_sub(threshold)).await;
+
+            discord_bot_client
+                .interaction_deferrals
+                .lock()
+                .await
+                .remove(&interaction_id);
+        });
+    }
+
+    /// Rewrites the interaction callback of an interaction the host deferred, message
+    /// responses become an update of the original response and deferrals get skipped.
+    pub async fn deferred_interaction_request(
+        &self,
+        request: DiscordRequests,
+    ) -> Result<Option<DiscordRequests>, String> {
+        let (interaction_id, interaction_token, with_response, body) = match request {
+            DiscordRequests::InteractionCallback(interaction_callback) => interaction_callback,
+            request => return Ok(Some(request)),
+        };
+
+        let interaction_deferral = match Id::new_checked(interaction_id) {
+            Some(interaction_id) => self
+                .interaction_deferrals
+                .lock()
+                .await
+                .remove(&interaction_id),
+            None => None,
+        };
+
+        let Some(interaction_deferral) = interaction_deferral else {
+            return Ok(Some(DiscordRequests::InteractionCallback((
+                interaction_id,
+                interaction_token,
+                with_response,
+                body,
+            ))));
+        };
+
+        let mut state = interaction_deferral.state.lock().await;
+
+        if *state != InteractionDeferralState::Deferred {
+            *state = InteractionDeferralState::Responded;
+
+            return Ok(Some(DiscordRequests::InteractionCallback((
+                interaction_id,
+                interaction_token,
+                with_response,
+                body,
+            ))));
+        }
+
+        *state = InteractionDeferralState::Responded;
+
+        let mut interaction_response = match sonic_rs::from_slice::<JsonValue>(&body) {
+            Ok(interaction_response) => interaction_response,
+            Err(err) => {
+                return Err(format!(
+                    "Something went wrong while deserializing the interaction callback of a deferred interaction, error: {err}"
+                ));
+            }
+        };
+
+        match interaction_response.get("type").and_then(JsonValue::as_u64) {
+            // Channel message with source
+            Some(4) => {
+                let data = interaction_response
+                    .get_mut("data")
+                    .map_or_else(|| sonic_rs::json!({}), JsonValue::take);
+
+                match sonic_rs::to_vec(&data) {
+                    Ok(body) => Ok(Some(DiscordRequests::UpdateInteractionOriginal((
+                        interaction_deferral.application_id.get(),
+                        interaction_token,
+                        Contents::Json(body),
+                    )))),
+                    Err(err) => Err(format!(
+                        "Something went wrong while serializing the response of a deferred interaction, error: {err}"
+                    )),
+                }
+            }
+            // Deferred channel message with source
+            Some(5) => Ok(None),
+            _ => Err(String::from(
+                "The host already deferred this interaction, only message responses can follow",
+            )),
+        }
+    }
+
+    async fn defer_interaction(
+        &self,
+        interaction_id: Id<InteractionMarker>,
+        interaction_token: &str,
+        ephemeral: bool,
+    ) -> Result<(), ()> {
+        let body = match sonic_rs::to_vec(&InteractionResponse {
+            kind: InteractionResponseType::DeferredChannelMessageWithSource,
+            data: ephemeral.then(|| InteractionResponseData {
+                flags: Some(MessageFlags::EPHEMERAL),
+                ..Default::default()
+            }),
+        }) {
+            Ok(body) => body,
+            Err(err) => {
+                error!(
+                    "Something went wrong while serializing a deferred interaction response, error: {}",
+                    &err
+                );
+                return Err(());
+            }
+        };
+
+        let request = match Request::builder(&Route::InteractionCallback {
+            interaction_id: interaction_id.get(),
+            interaction_token,
+            with_response: false,
+        })
+        .body(body)
+        .build()
+        {
+            Ok(request) => request,
+            Err(err) => {
+                error!(
+                    "Failed to build the deferred interaction response request, error: {}",
+                    &err
+                );
+                return Err(());
+            }
+        };
+
+        match self.http_client.request::<()>(request).await {
+            Ok(_) => Ok(()),
+            Err(err) => {
+                error!(
+                    "Something went wrong while deferring an interaction, error: {}",
+                    &err
+                );
+                Err(())
+            }
+        }
+    }
+}
@@ -1,29 +1,95 @@
 /* SPDX-License-Identifier: GPL-3.0-or-later */
 /* Copyright © 2026 Eduard Smet */
 
-use std::{any::Any, sync::Arc};
+use std::{any::Any, borrow::Cow, sync::Arc};
 
 use tracing::{debug, error};
-use twilight_gateway::Event;
-use twilight_model::application::interaction::InteractionData;
+use twilight_gateway::{Event, EventType};
+use twilight_model::application::interaction::{InteractionData, InteractionType};
 
 use crate::{
-    discord::DiscordBotClient,
-    plugins::discord_bot::plugin::discord_types::Events as DiscordEvents,
+    discord::DiscordBotClient, plugins::discord_bot::plugin::discord_types::Event as DiscordEvent,
     utils::channels::RuntimeMessages,
 };
 
+/// Generates the list of gateway events plugins can register by name and the function which
+/// serializes them, adding support for an event only requires adding it to the invocation.
+macro_rules! registrable_events {
+    ($($event_type:ident),* $(,)?) => {
+        /// Interaction create events are not part of this list as they get routed to a single
+        /// plugin based on the interaction data.
+        pub static REGISTRABLE_EVENT_TYPES: &[EventType] = &[$(EventType::$event_type),*];
+
+        fn serialize_event(event: &Event) -> Option<Result<Vec<u8>, sonic_rs::Error>> {
+            match event {
+                $(Event::$event_type(data) => Some(sonic_rs::to_vec(data)),)*
+                _ => None,
+            }
+        }
+    };
+}
+
+registrable_events![
+    ChannelCreate,
+    ChannelDelete,
+    ChannelUpdate,
+    GuildAuditLogEntryCreate,
+    GuildCreate,
+    GuildDelete,
+    GuildUpdate,
+    InviteCreate,
+    InviteDelete,
+    MemberAdd,
+    MemberRemove,
+    MemberUpdate,
+    MessageCreate,
+    MessageDelete,
+    MessageDeleteBulk,
+    MessageUpdate,
+    PresenceUpdate,
+    ReactionAdd,
+    ReactionRemove,
+    ReactionRemoveAll,
+    ReactionRemoveEmoji,
+    RoleCreate,
+    RoleDelete,
+    RoleUpdate,
+    ThreadCreate,
+    ThreadDelete,
+    ThreadListSync,
+    ThreadMemberUpdate,
+    ThreadMembersUpdate,
+    ThreadUpdate,
+    VoiceStateUpdate,
+];
+
+/// Parses a Discord gateway event name into an event type plugins can get permission for.
+pub fn permissible_event_type(name: &str) -> Option<EventType> {
+    let event_type = EventType::try_from(name).ok()?;
+
+    if event_type == EventType::InteractionCreate || REGISTRABLE_EVENT_TYPES.contains(&event_type) {
+        Some(event_type)
+    } else {
+        None
+    }
+}
+
 impl DiscordBotClient {
     #[allow(clippy::too_many_lines)]
     pub async fn handle_event(discord_bot_client: Arc<DiscordBotClient>, event: Event) {
+        let guild_id = event.guild_id();
+        let channel_id = Self::event_channel_id(&event);
+
         match event {
             Event::InteractionCreate(interaction_create) => {
                 match interaction_create.data.as_ref() {
+                    // Autocomplete interactions and context menu commands carry application
+                    // command data as well, all get routed to the plugin owning the command
                     Some(InteractionData::ApplicationCommand(command_data)) => {
                         let initialized_plugins =
                             discord_bot_client.plugin_registrations.read().await;
 
-                        let Some(plugin) = initialized_plugins
+                        let Some((plugin, requested_name)) = initialized_plugins
                             .discord_events
                             .interaction_create
                             .application_commands
@@ -32,59 +98,122 @@ impl DiscordBotClient {
                             return;
                         };
 
+                        if !discord_bot_client.plugin_scope_allows(plugin, guild_id, channel_id) {
+                            return;
+                        }
+
+                        // Plugins know their commands by the name they requested, not by the
+                        // name they got renamed or aliased to
+                        let interaction_create = if command_data.name == *requested_name {
+                            Cow::Borrowed(&*interaction_create)
+                        } else {
+                            let mut interaction_create = (*interaction_create).clone();
+
+                            if let Some(InteractionData::ApplicationCommand(command_data)) =
+                                interaction_create.0.data.as_mut()
+                            {
+                                command_data.name.clone_from(requested_name);
+                            }
+
+                            Cow::Owned(interaction_create)
+                        };
+
+                        if interaction_create.kind == InteractionType::ApplicationCommand
+                            && let Some(defer) = discord_bot_client
+                                .command_deferrals
+                                .get(plugin)
+                                .and_then(|command_deferrals| command_deferrals.get(requested_name))
+                        {
+                            Self::schedule_interaction_deferral(
+                                discord_bot_client.clone(),
+                                &interaction_create,
+                                defer,
+                            )
+                            .await;
+                        }
+
                         let _ = discord_bot_client
                             .runtime_tx
                             .send(RuntimeMessages::CallDiscordEvent(
                                 plugin.clone(),
-                                DiscordEvents::InteractionCreate(
-                                    sonic_rs::to_vec(&interaction_create).unwrap(),
-                                ),
+                                DiscordEvent {
+                                    name: String::from("INTERACTION_CREATE"),
+                                    data: sonic_rs::to_vec(&interaction_create).unwrap(),
+                                },
                             ))
                             .await;
                     }
                     Some(InteractionData::MessageComponent(message_component_interaction_data)) => {
-                        let initialized_plugins =
-                            discord_bot_client.plugin_registrations.read().await;
+                        let (plugin, interaction_create) = match discord_bot_client
+                            .strip_custom_id_namespace(&interaction_create)
+                        {
+                            Some(namespaced) => namespaced,
+                            None => {
+                                let initialized_plugins =
+                                    discord_bot_client.plugin_registrations.read().await;
 
-                        let Some(plugin) = initialized_plugins
-                            .discord_events
-                            .interaction_create
-                            .message_components
-                            .get(&message_component_interaction_data.custom_id)
-                        else {
-                            return;
+                                let Some(plugin) = initialized_plugins
+                                    .discord_events
+                                    .interaction_create
+                                    .message_components
+                                    .get(&message_component_interaction_data.custom_id)
+                                else {
+                                    return;
+                                };
+
+                                (plugin.clone(), Cow::Borrowed(&*interaction_create))
+                            }
                         };
 
+                        if !discord_bot_client.plugin_scope_allows(&plugin, guild_id, channel_id) {
+                            return;
+                        }
+
                         let _ = discord_bot_client
                             .runtime_tx
                             .send(RuntimeMessages::CallDiscordEvent(
-                                plugin.clone(),
-                                DiscordEvents::InteractionCreate(
-                                    sonic_rs::to_vec(&interaction_create).unwrap(),
-                                ),
+                                plugin,
+                                DiscordEvent {
+                                    name: String::from("INTERACTION_CREATE"),
+                                    data: sonic_rs::to_vec(&interaction_create).unwrap(),
+                                },
                             ))
                             .await;
                     }
                     Some(InteractionData::ModalSubmit(modal_interaction_data)) => {
-                        let initialized_plugins =
-                            discord_bot_client.plugin_registrations.read().await;
+                        let (plugin, interaction_create) = match discord_bot_client
+                            .strip_custom_id_namespace(&interaction_create)
+                        {
+                            Some(namespaced) => namespaced,
+                            None => {
+                                let initialized_plugins =
+                                    discord_bot_client.plugin_registrations.read().await;
 
-                        let Some(plugin) = initialized_plugins
-                            .discord_events
-                            .interaction_create
-                            .modals
-                            .get(&modal_interaction_data.custom_id)
-                        else {
-                            return;
+                                let Some(plugin) = initialized_plugins
+                                    .discord_events
+                                    .interaction_create
+                                    .modals
+                                    .get(&modal_interaction_data.custom_id)
+                                else {
+                                    return;
+                                };
+
+                                (plugin.clone(), Cow::Borrowed(&*interaction_create))
+                            }
                         };
 
+                        if !discord_bot_client.plugin_scope_allows(&plugin, guild_id, channel_id) {
+                            return;
+                        }
+
                         let _ = discord_bot_client
                             .runtime_tx
                             .send(RuntimeMessages::CallDiscordEvent(
-                                plugin.clone(),
-                                DiscordEvents::InteractionCreate(
-                                    sonic_rs::to_vec(&interaction_create).unwrap(),
-                                ),
+                                plugin,
+                                DiscordEvent {
+                                    name: String::from("INTERACTION_CREATE"),
+                                    data: sonic_rs::to_vec(&interaction_create).unwrap(),
+                                },
                             ))
                             .await;
                     }
@@ -95,137 +224,50 @@ impl DiscordBotClient {
                     ),
                 }
             }
-            Event::MessageCreate(message_create) => {
-                for plugin in &discord_bot_client
-                    .plugin_registrations
-                    .read()
-                    .await
-                    .discord_events
-                    .message_create
-                {
-                    let _ = discord_bot_client
-                        .runtime_tx
-                        .send(RuntimeMessages::CallDiscordEvent(
-                            plugin.clone(),
-                            DiscordEvents::MessageCreate(
-                                sonic_rs::to_vec(&message_create).unwrap(),
-                            ),
-                        ))
-                        .await;
-                }
-            }
-            Event::ThreadCreate(thread_create) => {
-                for plugin in &discord_bot_client
-                    .plugin_registrations
-                    .read()
-                    .await
-                    .discord_events
-                    .thread_create
-                {
-                    let _ = discord_bot_client
-                        .runtime_tx
-                        .send(RuntimeMessages::CallDiscordEvent(
-                            plugin.clone(),
-                            DiscordEvents::ThreadCreate(sonic_rs::to_vec(&thread_create).unwrap()),
-                        ))
-                        .await;
-                }
-            }
-            Event::ThreadDelete(thread_delete) => {
-                for plugin in &discord_bot_client
-                    .plugin_registrations
-                    .read()
-                    .await
-                    .discord_events
-                    .thread_delete
-                {
-                    let _ = discord_bot_client
-                        .runtime_tx
-                        .send(RuntimeMessages::CallDiscordEvent(
-                            plugin.clone(),
-                            DiscordEvents::ThreadDelete(sonic_rs::to_vec(&thread_delete).unwrap()),
-                        ))
-                        .await;
-                }
-            }
-            Event::ThreadListSync(thread_list_sync) => {
-                for plugin in &discord_bot_client
-                    .plugin_registrations
-                    .read()
-                    .await
-                    .discord_events
-                    .thread_list_sync
-                {
-                    let _ = discord_bot_client
-                        .runtime_tx
-                        .send(RuntimeMessages::CallDiscordEvent(
-                            plugin.clone(),
-                            DiscordEvents::ThreadListSync(
-                                sonic_rs::to_vec(&thread_list_sync).unwrap(),
-                            ),
-                        ))
-                        .await;
-                }
-            }
-            Event::ThreadMemberUpdate(thread_member_update) => {
-                for plugin in &discord_bot_client
-                    .plugin_registrations
-                    .read()
-                    .await
-                    .discord_events
-                    .thread_member_update
-                {
-                    let _ = discord_bot_client
-                        .runtime_tx
-                        .send(RuntimeMessages::CallDiscordEvent(
-                            plugin.clone(),
-                            DiscordEvents::ThreadMemberUpdate(
-                                sonic_rs::to_vec(&thread_member_update).unwrap(),
-                            ),
-                        ))
-                        .await;
-                }
-            }
-            Event::ThreadMembersUpdate(thread_members_update) => {
-                for plugin in &discord_bot_client
-                    .plugin_registrations
-                    .read()
-                    .await
-                    .discord_events
-                    .thread_members_update
-                {
-                    let _ = discord_bot_client
-                        .runtime_tx
-                        .send(RuntimeMessages::CallDiscordEvent(
-                            plugin.clone(),
-                            DiscordEvents::ThreadMembersUpdate(
-                                sonic_rs::to_vec(&thread_members_update).unwrap(),
-                            ),
-                        ))
-                        .await;
-                }
-            }
-            Event::ThreadUpdate(thread_update) => {
-                for plugin in &discord_bot_client
-                    .plugin_registrations
-                    .read()
-                    .await
-                    .discord_events
-                    .thread_update
-                {
+            event => {
+                let event_type = event.kind();
+
+                let plugin_registrations = discord_bot_client.plugin_registrations.read().await;
+
+                let Some(plugins) = plugin_registrations.discord_events.events.get(&event_type)
+                else {
+                    debug!(
+                        "Received an event no plugin registered: {}",
+                        &event_type.name().unwrap_or("[No event kind name]")
+                    );
+                    return;
+                };
+
+                let data = match serialize_event(&event) {
+                    Some(Ok(data)) => data,
+                    Some(Err(err)) => {
+                        error!(
+                            "Something went wrong while serializing the {} event, error: {}",
+                            &event_type.name().unwrap_or("[No event kind name]"),
+                            &err
+                        );
+                        return;
+                    }
+                    None => return,
+                };
+
+                for plugin in plugins {
+                    if !discord_bot_client.plugin_scope_allows(plugin, guild_id, channel_id) {
+                        continue;
+                    }
+
                     let _ = discord_bot_client
                         .runtime_tx
                         .send(RuntimeMessages::CallDiscordEvent(
                             plugin.clone(),
-                            DiscordEvents::ThreadUpdate(sonic_rs::to_vec(&thread_update).unwrap()),
+                            DiscordEvent {
+                                name: String::from(event_type.name().unwrap_or_default()),
+                                data: data.clone(),
+                            },
                         ))
                         .await;
                 }
             }
-            _ => debug!(
-                "Received an unhandled event: {}",
-                &event.kind().name().unwrap_or("[No event kind name]")
-            ),
         }
     }
 }
@@ -0,0 +1,177 @@
+/* SPDX-License-Identifier: GPL-3.0-or-later */
+/* Copyright © 2026 Eduard Smet */
+
+use std::{collections::HashMap, fmt};
+
+use serde::{
+    Deserializer,
+    de::{self, SeqAccess, Visitor},
+};
+use tracing::{info, warn};
+use twilight_gateway::{EventType, Intents};
+
+/// The intents which have to be enabled for the bot in the Discord developer portal.
+const PRIVILEGED_INTENTS: Intents = Intents::GUILD_MEMBERS
+    .union(Intents::GUILD_PRESENCES)
+    .union(Intents::MESSAGE_CONTENT);
+
+/// The intents needed to receive an event.
+fn event_intents(event_type: EventType) -> Intents {
+    match event_type {
+        EventType::ChannelCreate
+        | EventType::ChannelDelete
+        | EventType::ChannelUpdate
+        | EventType::GuildCreate
+        | EventType::GuildDelete
+        | EventType::GuildUpdate
+        | EventType::RoleCreate
+        | EventType::RoleDelete
+        | EventType::RoleUpdate
+        | EventType::ThreadCreate
+        | EventType::ThreadDelete
+        | EventType::ThreadListSync
+        | EventType::ThreadMemberUpdate
+        | EventType::ThreadMembersUpdate
+        | EventType::ThreadUpdate => Intents::GUILDS,
+        EventType::GuildAuditLogEntryCreate => Intents::GUILD_MODERATION,
+        EventType::InviteCreate | EventType::InviteDelete => Intents::GUILD_INVITES,
+        EventType::MemberAdd | EventType::MemberRemove | EventType::MemberUpdate => {
+            Intents::GUILD_MEMBERS
+        }
+        EventType::MessageCreate | EventType::MessageUpdate => {
+            Intents::GUILD_MESSAGES | Intents::DIRECT_MESSAGES | Intents::MESSAGE_CONTENT
+        }
+        EventType::MessageDelete | EventType::MessageDeleteBulk => {
+            Intents::GUILD_MESSAGES | Intents::DIRECT_MESSAGES
+        }
+        EventType::PresenceUpdate => Intents::GUILD_PRESENCES,
+        EventType::ReactionAdd
+        | EventType::ReactionRemove
+        | EventType::ReactionRemoveAll
+        | EventType::ReactionRemoveEmoji => {
+            Intents::GUILD_MESSAGE_REACTIONS | Intents::DIRECT_MESSAGE_REACTIONS
+        }
+        EventType::VoiceStateUpdate => Intents::GUILD_VOICE_STATES,
+        _ => Intents::empty(),
+    }
+}
+
+/// Derives the intents from the Discord events the plugins registered, guilds are always
+/// requested as the cache and shard routing rely on them. Privileged intents only get derived
+/// when the config opts into them, as Discord refuses the connection when they are not enabled
+/// in the developer portal. When the config overrides the intents, a warning gets logged for
+/// every needed intent it leaves out.
+pub fn intents(
+    config_intents: Option<Intents>,
+    config_privileged_intents: Option<Intents>,
+    registered_events: &HashMap<EventType, Vec<String>>,
+) -> Intents {
+    let allowed_privileged_intents = config_privileged_intents.unwrap_or(Intents::empty());
+
+    let mut derived_intents = Intents::GUILDS;
+
+    for (event_type, plugin_uids) in registered_events {
+        let event_intents = event_intents(*event_type);
+
+        let denied_intents = event_intents
+            .intersection(PRIVILEGED_INTENTS)
+            .difference(allowed_privileged_intents);
+
+        if config_intents.is_none() {
+            for (name, _) in denied_intents.iter_names() {
+                warn!(
+                    "The {event_type:?} registrations of the {} plugins need the privileged {name} intent which is not enabled in the privileged intents of the config, they will not receive the events or data that need it",
+                    plugin_uids.join(", ")
+                );
+            }
+        }
+
+        derived_intents |= event_intents.difference(denied_intents);
+    }
+
+    let intents = match config_intents {
+        Some(config_intents) => {
+            for (name, _) in derived_intents.difference(config_intents).iter_names() {
+                warn!(
+                    "The {name} intent is needed by the Discord events the plugins registered but is not enabled in the config, these events will not be received"
+                );
+            }
+
+            config_intents
+        }
+        None => derived_intents,
+    };
+
+    for (name, _) in intents.intersection(PRIVILEGED_INTENTS).iter_names() {
+        warn!(
+            "The privileged {name} intent is requested, it has to be enabled for the bot in the Discord developer portal or the connection will be refused"
+        );
+    }
+
+    info!("Requesting the following Discord gateway intents: {intents:?}");
+
+    intents
+}
+
+/// Deserializes a list of privileged gateway intent names, other intents get rejected.
+pub fn deserialize_privileged_intents<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<Intents>, D::Error> {
+    let intents = deserialize_intents(deserializer)?;
+
+    if let Some(intents) = intents
+        && !PRIVILEGED_INTENTS.contains(intents)
+    {
+        let names = intents
+            .difference(PRIVILEGED_INTENTS)
+            .iter_names()
+            .map(|(name, _)| name)
+            .collect::<Vec<_>>();
+
+        return Err(de::Error::custom(format!(
+            "{} are not privileged intents, expected any of GUILD_MEMBERS, GUILD_PRESENCES, MESSAGE_CONTENT",
+            names.join(", ")
+        )));
+    }
+
+    Ok(intents)
+}
+
+/// Deserializes a list of gateway intent names (like `GUILD_MESSAGES` or `message_content`).
+pub fn deserialize_intents<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<Intents>, D::Error> {
+    struct IntentsVisitor;
+
+    impl<'de> Visitor<'de> for IntentsVisitor {
+        type Value = Intents;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            formatter.write_str("a list of gateway intents")
+        }
+
+        fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+            let mut intents = Intents::empty();
+
+            while let Some(name) = seq.next_element::<String>()? {
+                let Some(intent) = Intents::from_name(&name.to_uppercase()) else {
+                    let names = Intents::all()
+                        .iter_names()
+                        .map(|(name, _)| name)
+                        .collect::<Vec<_>>();
+
+                    return Err(de::Error::custom(format!(
+                        "unknown intent `{name}`, expected one of {}",
+                        names.join(", ")
+                    )));
+                };
+
+                intents |= intent;
+            }
+
+            Ok(intents)
+        }
+    }
+
+    deserializer.deserialize_seq(IntentsVisitor).map(Some)
+}
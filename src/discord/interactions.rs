@@ -1,121 +1,338 @@
 /* SPDX-License-Identifier: GPL-3.0-or-later */
 /* Copyright © 2026 Eduard Smet */
 
-use std::collections::HashMap;
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+    sync::Arc,
+};
 
+use sonic_rs::{JsonValueMutTrait, JsonValueTrait, Value as JsonValue};
+use tokio::task::JoinSet;
 use tracing::{error, info};
-use twilight_http::{request::Request, routing::Route};
+use twilight_http::{Client, request::Request, routing::Route};
 use twilight_model::{
-    application::command::Command,
+    application::command::{Command, CommandType},
     id::{
         Id,
-        marker::{ApplicationMarker, CommandMarker},
+        marker::{ApplicationMarker, CommandMarker, GuildMarker},
     },
 };
 
-use crate::{discord::DiscordBotClient, plugins::PluginRegistrationRequestsApplicationCommand};
+use crate::{discord::DiscordBotClient, plugins::commands::PluginApplicationCommand};
+
+const CURRENT_USER_GUILDS_PAGE_SIZE: u16 = 200;
+
+const GUILD_COMMANDS_CONCURRENCY: usize = 5;
+
+/// Fields Discord fills in when a command omits them, only compared when the plugin sets them.
+const DISCORD_DEFAULTED_FIELDS: [&str; 4] = [
+    "contexts",
+    "integration_types",
+    "dm_permission",
+    "default_member_permissions",
+];
+
+/// Option fields which are false when omitted.
+const FALSE_DEFAULTED_FIELDS: [&str; 3] = ["nsfw", "required", "autocomplete"];
+
+/// The changes needed to bring the application commands of one scope, global or a single
+/// guild, in line with the plugins.
+pub struct ApplicationCommandScopeDiff {
+    pub guild_id: Option<Id<GuildMarker>>,
+    pub create: Vec<String>,
+    pub update: Vec<String>,
+    pub delete: Vec<String>,
+    commands: Vec<Command>,
+    existing_commands: Vec<Command>,
+}
+
+impl ApplicationCommandScopeDiff {
+    pub fn is_empty(&self) -> bool {
+        self.create.is_empty() && self.update.is_empty() && self.delete.is_empty()
+    }
+
+    pub fn changes(&self) -> Vec<String> {
+        let scope = match self.guild_id {
+            Some(guild_id) => format!("guild {guild_id}"),
+            None => String::from("global"),
+        };
+
+        [
+            ("create", &self.create),
+            ("update", &self.update),
+            ("delete", &self.delete),
+        ]
+        .into_iter()
+        .flat_map(|(action, names)| {
+            names
+                .iter()
+                .map(move |label| format!("{action} {label} ({scope})"))
+        })
+        .collect()
+    }
+}
 
 impl DiscordBotClient {
     pub async fn application_command_registrations(
         &self,
-        discord_application_command_registration_request: Vec<
-            PluginRegistrationRequestsApplicationCommand,
-        >,
+        commands: Vec<PluginApplicationCommand>,
     ) -> Result<(), ()> {
-        let mut discord_commands = HashMap::new();
+        let application_id = application_id(&self.http_client).await?;
 
-        let mut commands = HashMap::new();
+        let diffs = application_command_diffs(
+            &self.http_client,
+            application_id,
+            &commands,
+            self.keep_unknown_commands,
+            self.sweep_guild_commands,
+        )
+        .await?;
 
-        for command in discord_application_command_registration_request {
-            let command_data = match sonic_rs::from_slice::<Command>(&command.data) {
-                Ok(command) => command,
-                Err(err) => {
-                    error!(
-                        "Something went wrong while deserializing a command from the {} plugin requested to register, error: {}",
-                        &command.plugin_id, &err
-                    );
-                    continue;
-                }
-            };
+        let mut command_ids = HashMap::new();
 
-            commands
-                .entry(command_data.name.clone())
-                .or_insert(vec![])
-                .push((command.plugin_id, command_data.clone()));
+        for diff in &diffs {
+            for change in diff.changes() {
+                info!("Application command change: {change}");
+            }
+
+            if let Ok(scope_command_ids) =
+                apply_application_command_diff(&self.http_client, application_id, diff).await
+            {
+                command_ids.extend(scope_command_ids);
+            }
         }
 
-        let application_id = match self.http_client.current_user_application().await {
-            Ok(response) => match response.model().await {
-                Ok(application) => application.id,
-                Err(err) => {
+        let mut plugin_registrations = self.plugin_registrations.write().await;
+
+        for command in commands {
+            match command_ids.get(&(
+                command.command.guild_id,
+                command.command.kind,
+                command.command.name.clone(),
+            )) {
+                Some(command_id) => {
+                    plugin_registrations
+                        .discord_events
+                        .interaction_create
+                        .application_commands
+                        .insert(*command_id, (command.plugin_id, command.requested_name));
+                }
+                None => {
                     error!(
-                        "Something went wrong while deserializing the application data, error: {}",
-                        &err
+                        "Failed to register the {} command from the {} plugin",
+                        &command.command.name, &command.plugin_id
                     );
-                    return Err(());
                 }
-            },
-            Err(err) => {
-                error!(
-                    "Something went wrong while requesting the application data, error: {}",
-                    &err
-                );
-                return Err(());
             }
-        };
+        }
 
-        let global_discord_commands_request = match Request::builder(&Route::GetGlobalCommands {
-            application_id: application_id.get(),
-            with_localizations: Some(true),
-        })
-        .build()
-        {
-            Ok(global_discord_commands_request) => global_discord_commands_request,
+        Ok(())
+    }
+}
+
+/// Syncs the application commands without connecting to the gateway, when `dry_run` is set the
+/// changes only get planned.
+pub async fn sync_application_commands(
+    token: String,
+    commands: &[PluginApplicationCommand],
+    keep_unknown_commands: bool,
+    sweep_guild_commands: bool,
+    dry_run: bool,
+) -> Result<Vec<ApplicationCommandScopeDiff>, ()> {
+    rustls::crypto::aws_lc_rs::default_provider()
+        .install_default()
+        .unwrap();
+
+    let http_client = Arc::new(Client::new(token));
+
+    let application_id = application_id(&http_client).await?;
+
+    let diffs = application_command_diffs(
+        &http_client,
+        application_id,
+        commands,
+        keep_unknown_commands,
+        sweep_guild_commands,
+    )
+    .await?;
+
+    if !dry_run {
+        for diff in &diffs {
+            apply_application_command_diff(&http_client, application_id, diff).await?;
+        }
+    }
+
+    Ok(diffs)
+}
+
+async fn application_id(http_client: &Client) -> Result<Id<ApplicationMarker>, ()> {
+    match http_client.current_user_application().await {
+        Ok(response) => match response.model().await {
+            Ok(application) => Ok(application.id),
             Err(err) => {
                 error!(
-                    "Failed to build the get global commands request, error: {}",
+                    "Something went wrong while deserializing the application data, error: {}",
                     &err
                 );
-                return Err(());
+                Err(())
             }
-        };
+        },
+        Err(err) => {
+            error!(
+                "Something went wrong while requesting the application data, error: {}",
+                &err
+            );
+            Err(())
+        }
+    }
+}
+
+async fn application_command_diffs(
+    http_client: &Arc<Client>,
+    application_id: Id<ApplicationMarker>,
+    commands: &[PluginApplicationCommand],
+    keep_unknown_commands: bool,
+    sweep_guild_commands: bool,
+) -> Result<Vec<ApplicationCommandScopeDiff>, ()> {
+    let mut existing_commands = BTreeMap::new();
+
+    existing_commands.insert(
+        None,
+        get_application_commands(http_client, application_id, None).await?,
+    );
+
+    // Only the guilds the commands get registered in are fetched, sweeping every guild the bot
+    // is in also deletes the commands of guilds the plugins no longer target
+    let guild_ids = if sweep_guild_commands && !keep_unknown_commands {
+        current_user_guild_ids(http_client)
+            .await?
+            .into_iter()
+            .collect::<BTreeSet<_>>()
+    } else {
+        commands
+            .iter()
+            .filter_map(|command| command.command.guild_id)
+            .collect()
+    };
+
+    let (mut guild_commands, failed_guild_ids) =
+        guild_application_commands(http_client, application_id, guild_ids).await;
+
+    existing_commands.append(&mut guild_commands);
 
-        match self
-            .http_client
-            .request::<Vec<Command>>(global_discord_commands_request)
-            .await
+    let mut desired_commands: BTreeMap<Option<Id<GuildMarker>>, Vec<Command>> = BTreeMap::new();
+
+    for command in commands {
+        desired_commands
+            .entry(command.command.guild_id)
+            .or_default()
+            .push(command.command.clone());
+    }
+
+    // Without the existing commands a bulk overwrite could delete commands which should be kept
+    for guild_id in failed_guild_ids {
+        error!(
+            "The application commands of guild {guild_id} could not be fetched, skipping the sync of its commands"
+        );
+
+        desired_commands.remove(&Some(guild_id));
+    }
+
+    for guild_id in existing_commands.keys() {
+        desired_commands.entry(*guild_id).or_default();
+    }
+
+    Ok(desired_commands
+        .into_iter()
+        .map(|(guild_id, commands)| {
+            application_command_scope_diff(
+                guild_id,
+                commands,
+                existing_commands.remove(&guild_id).unwrap_or_default(),
+                keep_unknown_commands,
+            )
+        })
+        .collect())
+}
+
+fn application_command_scope_diff(
+    guild_id: Option<Id<GuildMarker>>,
+    mut commands: Vec<Command>,
+    existing_commands: Vec<Command>,
+    keep_unknown_commands: bool,
+) -> ApplicationCommandScopeDiff {
+    let mut create = vec![];
+    let mut update = vec![];
+
+    for command in &commands {
+        match existing_commands
+            .iter()
+            .find(|existing_command| same_application_command(command, existing_command))
         {
-            Ok(response) => match response.model().await {
-                Ok(global_discord_commands) => {
-                    for global_discord_command in global_discord_commands {
-                        discord_commands
-                            .insert(global_discord_command.name.clone(), global_discord_command);
-                    }
-                }
-                Err(err) => {
-                    error!(
-                        "Something went wrong while deserializing the global application commands, error: {}",
-                        &err
-                    );
-                    return Err(());
-                }
-            },
-            Err(err) => {
-                error!(
-                    "Something went wrong while requesting the global application commands, error: {}",
-                    &err
-                );
-                return Err(());
-            }
+            Some(existing_command) if application_commands_eq(command, existing_command) => {}
+            Some(_) => update.push(application_command_label(command)),
+            None => create.push(application_command_label(command)),
         }
+    }
 
-        // TODO: Endpoint is limited to 200 guilds per request, pagination needs to be implemented.
-        let current_user_guilds = match self.http_client.current_user_guilds().await {
+    let mut unknown_commands = existing_commands
+        .iter()
+        .filter(|existing_command| {
+            !commands
+                .iter()
+                .any(|command| same_application_command(command, existing_command))
+        })
+        .cloned()
+        .collect::<Vec<_>>();
+
+    // A bulk overwrite deletes every command it does not contain, unknown commands which
+    // should be kept are sent back as is
+    let delete = if keep_unknown_commands {
+        for unknown_command in &mut unknown_commands {
+            unknown_command.id = None;
+            unknown_command.application_id = None;
+            unknown_command.guild_id = None;
+        }
+
+        commands.append(&mut unknown_commands);
+
+        vec![]
+    } else {
+        unknown_commands
+            .into_iter()
+            .map(|unknown_command| application_command_label(&unknown_command))
+            .collect()
+    };
+
+    ApplicationCommandScopeDiff {
+        guild_id,
+        create,
+        update,
+        delete,
+        commands,
+        existing_commands,
+    }
+}
+
+/// Pages through all guilds the bot is in.
+async fn current_user_guild_ids(http_client: &Client) -> Result<Vec<Id<GuildMarker>>, ()> {
+    let mut guild_ids = vec![];
+
+    loop {
+        let mut request = http_client
+            .current_user_guilds()
+            .limit(CURRENT_USER_GUILDS_PAGE_SIZE);
+
+        if let Some(guild_id) = guild_ids.last() {
+            request = request.after(*guild_id);
+        }
+
+        let current_user_guilds = match request.await {
             Ok(response) => match response.model().await {
                 Ok(current_user_guilds) => current_user_guilds,
                 Err(err) => {
                     error!(
-                        "Something went wrong while deserializing the global application commands, error: {}",
+                        "Something went wrong while deserializing the current user guilds, error: {}",
                         &err
                     );
                     return Err(());
@@ -123,253 +340,270 @@ impl DiscordBotClient {
             },
             Err(err) => {
                 error!(
-                    "Something went wrong while requesting the global application commands, error: {}",
+                    "Something went wrong while requesting the current user guilds, error: {}",
                     &err
                 );
                 return Err(());
             }
         };
 
-        for current_user_guild in current_user_guilds {
-            let guild_commands_request = match Request::builder(&Route::GetGuildCommands {
-                application_id: application_id.get(),
-                guild_id: current_user_guild.id.get(),
-                with_localizations: Some(true),
-            })
-            .build()
-            {
-                Ok(guild_commands_request) => guild_commands_request,
-                Err(err) => {
-                    error!(
-                        "Failed to build the get guild commands request, error: {}",
-                        &err
-                    );
-                    continue;
-                }
-            };
+        let page_size = current_user_guilds.len();
 
-            match self
-                .http_client
-                .request::<Vec<Command>>(guild_commands_request)
-                .await
-            {
-                Ok(response) => match response.model().await {
-                    Ok(single_guild_discord_commands) => {
-                        for single_guild_discord_command in single_guild_discord_commands {
-                            discord_commands.insert(
-                                single_guild_discord_command.name.clone(),
-                                single_guild_discord_command,
-                            );
-                        }
-                    }
-                    Err(err) => {
-                        error!(
-                            "Something went wrong while deserializing the guild application commands, error: {}",
-                            &err
-                        );
-                    }
-                },
-                Err(err) => {
-                    error!(
-                        "Something went wrong while requesting the guild application commands, error: {}",
-                        &err
-                    );
-                }
-            }
-        }
+        guild_ids.extend(
+            current_user_guilds
+                .into_iter()
+                .map(|current_user_guild| current_user_guild.id),
+        );
 
-        for mut commands_by_name in commands {
-            if commands_by_name.1.len() == 1 {
-                let command = commands_by_name.1.remove(0);
-
-                match self
-                    .register_application_command(application_id, &mut discord_commands, &command.1)
-                    .await
-                {
-                    Ok(command_id) => {
-                        self.plugin_registrations
-                            .write()
-                            .await
-                            .discord_events
-                            .interaction_create
-                            .application_commands
-                            .insert(command_id, command.0);
-                    }
-                    Err(()) => {
-                        error!(
-                            "Failed to register the {} command from the {} plugin",
-                            &command.1.name, &command.0
-                        );
-                    }
-                }
-            } else {
-                let mut command_name_occurence_count = 1;
-
-                for mut command in commands_by_name.1 {
-                    command.1.name += format!("~{command_name_occurence_count}").as_str();
-
-                    match self
-                        .register_application_command(
-                            application_id,
-                            &mut discord_commands,
-                            &command.1,
-                        )
-                        .await
-                    {
-                        Ok(command_id) => {
-                            self.plugin_registrations
-                                .write()
-                                .await
-                                .discord_events
-                                .interaction_create
-                                .application_commands
-                                .insert(command_id, command.0);
-                        }
-                        Err(()) => {
-                            error!(
-                                "Failed to register the {} command from the {} plugin",
-                                &command.1.name, &command.0
-                            );
-                        }
-                    }
-
-                    command_name_occurence_count += 1;
-                }
-            }
+        if page_size < usize::from(CURRENT_USER_GUILDS_PAGE_SIZE) {
+            return Ok(guild_ids);
         }
+    }
+}
 
-        self.delete_old_application_commands(application_id, &discord_commands)
-            .await?;
+/// Fetches the commands of the guilds with at most `GUILD_COMMANDS_CONCURRENCY` requests in
+/// flight, guilds whose request failed are returned separately.
+async fn guild_application_commands(
+    http_client: &Arc<Client>,
+    application_id: Id<ApplicationMarker>,
+    guild_ids: impl IntoIterator<Item = Id<GuildMarker>>,
+) -> (
+    BTreeMap<Option<Id<GuildMarker>>, Vec<Command>>,
+    Vec<Id<GuildMarker>>,
+) {
+    let mut guild_commands = BTreeMap::new();
+    let mut pending_guild_ids = HashSet::new();
 
-        Ok(())
-    }
+    let mut guild_ids = guild_ids.into_iter();
+    let mut tasks = JoinSet::new();
 
-    async fn register_application_command(
-        &self,
-        application_id: Id<ApplicationMarker>,
-        discord_commands: &mut HashMap<String, Command>,
-        command: &Command,
-    ) -> Result<Id<CommandMarker>, ()> {
-        let request = if let Some(discord_command) = discord_commands.remove(&command.name) {
-            let route = if let Some(guild_id) = command.guild_id {
-                Route::UpdateGuildCommand {
-                    application_id: application_id.get(),
-                    command_id: discord_command.id.unwrap().get(),
-                    guild_id: guild_id.get(),
-                }
-            } else {
-                Route::UpdateGlobalCommand {
-                    application_id: application_id.get(),
-                    command_id: discord_command.id.unwrap().get(),
-                }
-            };
+    loop {
+        while tasks.len() < GUILD_COMMANDS_CONCURRENCY
+            && let Some(guild_id) = guild_ids.next()
+        {
+            let http_client = http_client.clone();
 
-            match Request::builder(&route)
-                .body(sonic_rs::to_vec(command).unwrap())
-                .build()
-            {
-                Ok(request) => request,
-                Err(err) => {
-                    error!(
-                        "Failed to build the create global command request, error: {}",
-                        &err
-                    );
-                    return Err(());
-                }
-            }
-        } else {
-            let route = if let Some(guild_id) = command.guild_id {
-                Route::CreateGuildCommand {
-                    application_id: application_id.get(),
-                    guild_id: guild_id.get(),
-                }
-            } else {
-                Route::CreateGlobalCommand {
-                    application_id: application_id.get(),
-                }
-            };
+            pending_guild_ids.insert(guild_id);
 
-            match Request::builder(&route)
-                .body(sonic_rs::to_vec(command).unwrap())
-                .build()
-            {
-                Ok(request) => request,
-                Err(err) => {
-                    error!(
-                        "Failed to build the create global command request, error: {}",
-                        &err
-                    );
-                    return Err(());
-                }
-            }
+            tasks.spawn(async move {
+                (
+                    guild_id,
+                    get_application_commands(&http_client, application_id, Some(guild_id)).await,
+                )
+            });
+        }
+
+        // Guilds whose task failed remain pending
+        let Some(result) = tasks.join_next().await else {
+            return (guild_commands, pending_guild_ids.into_iter().collect());
         };
 
-        match self.http_client.request::<Command>(request).await {
-            Ok(response) => match response.model().await {
-                Ok(command) => Ok(command.id.unwrap()),
-                Err(err) => {
-                    error!(
-                        "Something went wrong while deserializing the create global command response, error: {}",
-                        &err
-                    );
-                    Err(())
-                }
-            },
+        match result {
+            Ok((guild_id, Ok(commands))) => {
+                pending_guild_ids.remove(&guild_id);
+                guild_commands.insert(Some(guild_id), commands);
+            }
+            Ok((_, Err(()))) => {}
             Err(err) => {
                 error!(
-                    "Something went wrong while requesting a global command creation, error: {}",
+                    "Something went wrong while fetching the guild application commands, error: {}",
                     &err
                 );
+            }
+        }
+    }
+}
+
+/// Applies a scope its changes with a single bulk overwrite and returns the IDs of its
+/// commands, unchanged scopes do not get requested.
+async fn apply_application_command_diff(
+    http_client: &Client,
+    application_id: Id<ApplicationMarker>,
+    diff: &ApplicationCommandScopeDiff,
+) -> Result<HashMap<(Option<Id<GuildMarker>>, CommandType, String), Id<CommandMarker>>, ()> {
+    let commands = if diff.is_empty() {
+        diff.existing_commands.clone()
+    } else {
+        set_application_commands(http_client, application_id, diff.guild_id, &diff.commands).await?
+    };
+
+    Ok(commands
+        .into_iter()
+        .filter_map(|command| {
+            command
+                .id
+                .map(|command_id| ((diff.guild_id, command.kind, command.name), command_id))
+        })
+        .collect())
+}
+
+async fn get_application_commands(
+    http_client: &Client,
+    application_id: Id<ApplicationMarker>,
+    guild_id: Option<Id<GuildMarker>>,
+) -> Result<Vec<Command>, ()> {
+    let route = match guild_id {
+        Some(guild_id) => Route::GetGuildCommands {
+            application_id: application_id.get(),
+            guild_id: guild_id.get(),
+            with_localizations: Some(true),
+        },
+        None => Route::GetGlobalCommands {
+            application_id: application_id.get(),
+            with_localizations: Some(true),
+        },
+    };
+
+    let request = match Request::builder(&route).build() {
+        Ok(request) => request,
+        Err(err) => {
+            error!(
+                "Failed to build the get commands request, guild id: {:?}, error: {}",
+                &guild_id, &err
+            );
+            return Err(());
+        }
+    };
+
+    match http_client.request::<Vec<Command>>(request).await {
+        Ok(response) => match response.model().await {
+            Ok(commands) => Ok(commands),
+            Err(err) => {
+                error!(
+                    "Something went wrong while deserializing the application commands, guild id: {:?}, error: {}",
+                    &guild_id, &err
+                );
                 Err(())
             }
+        },
+        Err(err) => {
+            error!(
+                "Something went wrong while requesting the application commands, guild id: {:?}, error: {}",
+                &guild_id, &err
+            );
+            Err(())
         }
     }
+}
 
-    async fn delete_old_application_commands(
-        &self,
-        application_id: Id<ApplicationMarker>,
-        discord_commands: &HashMap<String, Command>,
-    ) -> Result<(), ()> {
-        for discord_command in discord_commands.values() {
-            let route = match discord_command.guild_id {
-                Some(guild_id) => Route::DeleteGuildCommand {
-                    application_id: application_id.get(),
-                    command_id: discord_command.id.unwrap().get(),
-                    guild_id: guild_id.get(),
-                },
-                None => Route::DeleteGlobalCommand {
-                    application_id: application_id.get(),
-                    command_id: discord_command.id.unwrap().get(),
-                },
-            };
-
-            let request = match Request::builder(&route).build() {
-                Ok(request) => request,
-                Err(err) => {
-                    error!(
-                        "Failed to build the create global command request, error: {}",
-                        &err
-                    );
-                    return Err(());
-                }
-            };
+async fn set_application_commands(
+    http_client: &Client,
+    application_id: Id<ApplicationMarker>,
+    guild_id: Option<Id<GuildMarker>>,
+    commands: &[Command],
+) -> Result<Vec<Command>, ()> {
+    let route = match guild_id {
+        Some(guild_id) => Route::SetGuildCommands {
+            application_id: application_id.get(),
+            guild_id: guild_id.get(),
+        },
+        None => Route::SetGlobalCommands {
+            application_id: application_id.get(),
+        },
+    };
 
-            info!(
-                "Deleting the {} command, guild id: {:?}",
-                &discord_command.name, &discord_command.guild_id
+    let request = match Request::builder(&route)
+        .body(sonic_rs::to_vec(commands).unwrap())
+        .build()
+    {
+        Ok(request) => request,
+        Err(err) => {
+            error!(
+                "Failed to build the bulk overwrite commands request, guild id: {:?}, error: {}",
+                &guild_id, &err
             );
-            match self.http_client.request::<()>(request).await {
-                Ok(_) => (),
-                Err(err) => {
-                    error!(
-                        "Something went wrong while requesting a command deletion, error: {}",
-                        &err
-                    );
-                    return Err(());
-                }
+            return Err(());
+        }
+    };
+
+    match http_client.request::<Vec<Command>>(request).await {
+        Ok(response) => match response.model().await {
+            Ok(commands) => Ok(commands),
+            Err(err) => {
+                error!(
+                    "Something went wrong while deserializing the bulk overwrite commands response, guild id: {:?}, error: {}",
+                    &guild_id, &err
+                );
+                Err(())
             }
+        },
+        Err(err) => {
+            error!(
+                "Something went wrong while requesting a bulk overwrite of the commands, guild id: {:?}, error: {}",
+                &guild_id, &err
+            );
+            Err(())
         }
+    }
+}
 
-        Ok(())
+/// Discord identifies commands by their type and name, context menu commands can share a name
+/// with a chat input command.
+fn same_application_command(command: &Command, existing_command: &Command) -> bool {
+    command.kind == existing_command.kind && command.name == existing_command.name
+}
+
+pub fn application_command_label(command: &Command) -> String {
+    match command.kind {
+        CommandType::ChatInput => format!("/{}", &command.name),
+        CommandType::User => format!("{} (user command)", &command.name),
+        CommandType::Message => format!("{} (message command)", &command.name),
+        kind => format!("{} ({kind:?} command)", &command.name),
+    }
+}
+
+/// Compares commands structurally, ignoring the fields Discord assigns and values equal to
+/// their defaults.
+fn application_commands_eq(command: &Command, existing_command: &Command) -> bool {
+    let (Ok(mut command), Ok(mut existing_command)) = (
+        sonic_rs::to_value(command),
+        sonic_rs::to_value(existing_command),
+    ) else {
+        return false;
+    };
+
+    for value in [&mut command, &mut existing_command] {
+        strip_default_values(value);
+
+        if let Some(object) = value.as_object_mut() {
+            for key in ["id", "application_id", "guild_id", "version"] {
+                object.remove(&key);
+            }
+        }
+    }
+
+    if let (Some(object), Some(existing_object)) =
+        (command.as_object(), existing_command.as_object_mut())
+    {
+        existing_object
+            .retain(|key, _| object.contains_key(key) || !DISCORD_DEFAULTED_FIELDS.contains(&key));
+    }
+
+    command == existing_command
+}
+
+fn strip_default_values(value: &mut JsonValue) {
+    if let Some(object) = value.as_object_mut() {
+        for (_, value) in object.iter_mut() {
+            strip_default_values(value);
+        }
+
+        object.retain(|key, value| {
+            if value.is_null() {
+                false
+            } else if let Some(array) = value.as_array() {
+                !array.is_empty()
+            } else if let Some(object) = value.as_object() {
+                !object.is_empty()
+            } else if value.as_bool() == Some(false) {
+                !FALSE_DEFAULTED_FIELDS.contains(&key)
+            } else {
+                true
+            }
+        });
+    } else if let Some(array) = value.as_array_mut() {
+        for value in array.iter_mut() {
+            strip_default_values(value);
+        }
     }
 }
@@ -1,8 +1,11 @@
 /* SPDX-License-Identifier: GPL-3.0-or-later */
 /* Copyright © 2026 Eduard Smet */
 
-use twilight_http::{request::Request, routing::Route};
+pub mod raw_http;
+
+use twilight_http::{api_error::ApiError, error::ErrorType, request::Request, routing::Route};
 use twilight_model::{
+    application::command::CommandOptionChoice,
     gateway::{
         OpCode,
         payload::outgoing::{
@@ -11,6 +14,7 @@ use twilight_model::{
             update_voice_state::UpdateVoiceStateInfo,
         },
     },
+    http::interaction::{InteractionResponse, InteractionResponseData, InteractionResponseType},
     id::Id,
 };
 
@@ -18,27 +22,142 @@ use crate::{
     discord::DiscordBotClient,
     plugins::discord_bot::plugin::{
         discord_types::Contents,
-        host_functions::{DiscordRequests, DiscordResponses},
+        host_functions::{DiscordRequestError, DiscordRequests, DiscordResponses},
     },
 };
 
+impl From<String> for DiscordRequestError {
+    fn from(message: String) -> Self {
+        DiscordRequestError {
+            status: None,
+            code: None,
+            message,
+            retry_after: None,
+            sent: false,
+        }
+    }
+}
+
+impl From<twilight_http::Error> for DiscordRequestError {
+    fn from(err: twilight_http::Error) -> Self {
+        let message = format!("Something went wrong while making a Discord request, error: {err}");
+
+        match err.kind() {
+            ErrorType::Response { error, status, .. } => {
+                let (code, message, retry_after) = match error {
+                    ApiError::General(general_api_error) => (
+                        Some(general_api_error.code),
+                        general_api_error.message.clone(),
+                        None,
+                    ),
+                    ApiError::Ratelimited(ratelimited_api_error) => (
+                        None,
+                        ratelimited_api_error.message.clone(),
+                        Some(ratelimited_api_error.retry_after),
+                    ),
+                    _ => (None, message, None),
+                };
+
+                DiscordRequestError {
+                    status: Some(status.get()),
+                    code,
+                    message,
+                    retry_after,
+                    sent: true,
+                }
+            }
+            ErrorType::ServiceUnavailable { response } => DiscordRequestError {
+                status: Some(response.status().as_u16()),
+                code: None,
+                message,
+                retry_after: None,
+                sent: true,
+            },
+            ErrorType::Unauthorized => DiscordRequestError {
+                status: Some(401),
+                code: None,
+                message,
+                retry_after: None,
+                sent: true,
+            },
+            ErrorType::RequestTimedOut => DiscordRequestError {
+                status: None,
+                code: None,
+                message,
+                retry_after: None,
+                sent: true,
+            },
+            _ => DiscordRequestError::from(message),
+        }
+    }
+}
+
 impl DiscordBotClient {
-    #[allow(clippy::too_many_lines)]
     pub async fn request(
         &self,
         request: DiscordRequests,
-    ) -> Result<Option<DiscordResponses>, String> {
+    ) -> Result<Option<DiscordResponses>, DiscordRequestError> {
+        let Some(request) = self.prepare_request(request).await? else {
+            return Ok(None);
+        };
+
+        let response = self.http_client.request::<Vec<u8>>(request).await?;
+        let status = response.status().get();
+
+        let mut ratelimit_bucket = None;
+        let mut ratelimit_remaining = None;
+
+        for (name, value) in response.headers() {
+            match name {
+                "x-ratelimit-bucket" => {
+                    ratelimit_bucket = str::from_utf8(value).ok().map(String::from);
+                }
+                "x-ratelimit-remaining" => {
+                    ratelimit_remaining = str::from_utf8(value)
+                        .ok()
+                        .and_then(|value| value.parse::<u64>().ok());
+                }
+                _ => {}
+            }
+        }
+
+        match response.bytes().await {
+            Ok(bytes) => Ok(Some(DiscordResponses {
+                status,
+                ratelimit_bucket,
+                ratelimit_remaining,
+                body: if status == 204 || bytes.is_empty() {
+                    None
+                } else {
+                    Some(bytes)
+                },
+            })),
+            Err(err) => Err(DiscordRequestError {
+                status: Some(status),
+                code: None,
+                message: format!(
+                    "Something went wrong while reading a Discord response body, error: {err}"
+                ),
+                retry_after: None,
+                sent: true,
+            }),
+        }
+    }
+
+    /// Sends the shard message sender commands and builds the HTTP requests.
+    #[allow(clippy::too_many_lines)]
+    async fn prepare_request(&self, request: DiscordRequests) -> Result<Option<Request>, String> {
         let request = match request {
             // Shard message sender commands
             DiscordRequests::RequestGuildMembers((guild_id, body)) => {
-                let guild_id = Id::new(guild_id);
+                let Some(guild_id) = Id::new_checked(guild_id) else {
+                    return Err(String::from("The guild ID can not be zero"));
+                };
 
-                let guild_shard_message_sender = if let Some(guild_shard_message_sender) =
-                    self.shard_message_senders.read().await.get(&guild_id)
-                {
-                    guild_shard_message_sender.clone()
-                } else {
-                    return Err(String::from("No guild found"));
+                let Some(guild_shard_message_sender) =
+                    self.guild_shard_message_sender(guild_id).await
+                else {
+                    return Err(String::from("No shard found for the guild"));
                 };
 
                 let d = match sonic_rs::from_slice::<RequestGuildMembersInfo>(&body) {
@@ -65,28 +184,28 @@ impl DiscordBotClient {
                 ));
             }
             DiscordRequests::UpdateVoiceState((guild_id, body)) => {
-                let guild_id = Id::new(guild_id);
+                let Some(guild_id) = Id::new_checked(guild_id) else {
+                    return Err(String::from("The guild ID can not be zero"));
+                };
 
-                let guild_shard_message_sender = if let Some(guild_shard_message_sender) =
-                    self.shard_message_senders.read().await.get(&guild_id)
-                {
-                    guild_shard_message_sender.clone()
-                } else {
-                    return Err(String::from("No guild found"));
+                let Some(guild_shard_message_sender) =
+                    self.guild_shard_message_sender(guild_id).await
+                else {
+                    return Err(String::from("No shard found for the guild"));
                 };
 
                 let d = match sonic_rs::from_slice::<UpdateVoiceStateInfo>(&body) {
                     Ok(d) => d,
                     Err(err) => {
                         return Err(format!(
-                            "Something went wrong while deserializing RequestGuildMembersInfo, error: {err}",
+                            "Something went wrong while deserializing UpdateVoiceStateInfo, error: {err}",
                         ));
                     }
                 };
 
                 let update_voice_state = UpdateVoiceState {
                     d,
-                    op: OpCode::RequestGuildMembers,
+                    op: OpCode::VoiceStateUpdate,
                 };
 
                 let _ = guild_shard_message_sender.command(&update_voice_state);
@@ -94,29 +213,16 @@ impl DiscordBotClient {
                 None
             }
             DiscordRequests::UpdatePresence(body) => {
-                let guild_shard_message_sender = if let Some(guild_shard_message_sender) =
-                    self.shard_message_senders.read().await.values().next()
-                {
-                    guild_shard_message_sender.clone()
-                } else {
-                    return Err(String::from("No guild found"));
-                };
-
                 let d = match sonic_rs::from_slice::<UpdatePresencePayload>(&body) {
                     Ok(d) => d,
                     Err(err) => {
                         return Err(format!(
-                            "Something went wrong while deserializing RequestGuildMembersInfo, error: {err}",
+                            "Something went wrong while deserializing UpdatePresencePayload, error: {err}",
                         ));
                     }
                 };
 
-                let update_voice_state = UpdatePresence {
-                    d,
-                    op: OpCode::RequestGuildMembers,
-                };
-
-                let _ = guild_shard_message_sender.command(&update_voice_state);
+                self.update_presence(d).await;
 
                 None
             }
@@ -411,11 +517,61 @@ impl DiscordBotClient {
             DiscordRequests::UpdateInteractionOriginal((
                 application_id,
                 interaction_token,
-                body,
+                content,
             )) => {
-                match Request::builder(&Route::UpdateInteractionOriginal {
+                let request_builder = Request::builder(&Route::UpdateInteractionOriginal {
                     application_id,
                     interaction_token: &interaction_token,
+                });
+
+                let request_builder = match content {
+                    Contents::Json(bytes) => request_builder.body(bytes),
+                    Contents::Form(buffer) => match request_builder.multipart(buffer) {
+                        Ok(request) => request,
+                        Err(err) => {
+                            return Err(err.to_string());
+                        }
+                    },
+                };
+
+                match request_builder.build() {
+                    Ok(request) => Some(request),
+                    Err(err) => {
+                        return Err(format!(
+                            "Something went wrong while building a Discord request, error: {err}"
+                        ));
+                    }
+                }
+            }
+            DiscordRequests::AutocompleteResult((interaction_id, interaction_token, choices)) => {
+                let choices = match sonic_rs::from_slice::<Vec<CommandOptionChoice>>(&choices) {
+                    Ok(choices) => choices,
+                    Err(err) => {
+                        return Err(format!(
+                            "Something went wrong while deserializing the autocomplete choices, error: {err}",
+                        ));
+                    }
+                };
+
+                let body = match sonic_rs::to_vec(&InteractionResponse {
+                    kind: InteractionResponseType::ApplicationCommandAutocompleteResult,
+                    data: Some(InteractionResponseData {
+                        choices: Some(choices),
+                        ..Default::default()
+                    }),
+                }) {
+                    Ok(body) => body,
+                    Err(err) => {
+                        return Err(format!(
+                            "Something went wrong while serializing the autocomplete result, error: {err}",
+                        ));
+                    }
+                };
+
+                match Request::builder(&Route::InteractionCallback {
+                    interaction_id,
+                    interaction_token: &interaction_token,
+                    with_response: false,
                 })
                 .body(body)
                 .build()
@@ -428,17 +584,150 @@ impl DiscordBotClient {
                     }
                 }
             }
+            DiscordRequests::GetInteractionOriginal((application_id, interaction_token)) => {
+                match Request::builder(&Route::GetInteractionOriginal {
+                    application_id,
+                    interaction_token: &interaction_token,
+                })
+                .build()
+                {
+                    Ok(request) => Some(request),
+                    Err(err) => {
+                        return Err(format!(
+                            "Something went wrong while building a Discord request, error: {err}"
+                        ));
+                    }
+                }
+            }
+            DiscordRequests::DeleteInteractionOriginal((application_id, interaction_token)) => {
+                match Request::builder(&Route::DeleteInteractionOriginal {
+                    application_id,
+                    interaction_token: &interaction_token,
+                })
+                .build()
+                {
+                    Ok(request) => Some(request),
+                    Err(err) => {
+                        return Err(format!(
+                            "Something went wrong while building a Discord request, error: {err}"
+                        ));
+                    }
+                }
+            }
+            DiscordRequests::CreateFollowup((application_id, interaction_token, content)) => {
+                let request_builder = Request::builder(&Route::ExecuteWebhook {
+                    thread_id: None,
+                    token: &interaction_token,
+                    wait: None,
+                    webhook_id: application_id,
+                    with_components: None,
+                });
+
+                let request_builder = match content {
+                    Contents::Json(bytes) => request_builder.body(bytes),
+                    Contents::Form(buffer) => match request_builder.multipart(buffer) {
+                        Ok(request) => request,
+                        Err(err) => {
+                            return Err(err.to_string());
+                        }
+                    },
+                };
+
+                match request_builder.build() {
+                    Ok(request) => Some(request),
+                    Err(err) => {
+                        return Err(format!(
+                            "Something went wrong while building a Discord request, error: {err}"
+                        ));
+                    }
+                }
+            }
+            DiscordRequests::GetFollowup((application_id, interaction_token, message_id)) => {
+                match Request::builder(&Route::GetFollowupMessage {
+                    application_id,
+                    interaction_token: &interaction_token,
+                    thread_id: None,
+                    message_id,
+                })
+                .build()
+                {
+                    Ok(request) => Some(request),
+                    Err(err) => {
+                        return Err(format!(
+                            "Something went wrong while building a Discord request, error: {err}"
+                        ));
+                    }
+                }
+            }
+            DiscordRequests::UpdateFollowup((
+                application_id,
+                interaction_token,
+                message_id,
+                content,
+            )) => {
+                let request_builder = Request::builder(&Route::UpdateWebhookMessage {
+                    message_id,
+                    thread_id: None,
+                    token: &interaction_token,
+                    webhook_id: application_id,
+                });
+
+                let request_builder = match content {
+                    Contents::Json(bytes) => request_builder.body(bytes),
+                    Contents::Form(buffer) => match request_builder.multipart(buffer) {
+                        Ok(request) => request,
+                        Err(err) => {
+                            return Err(err.to_string());
+                        }
+                    },
+                };
+
+                match request_builder.build() {
+                    Ok(request) => Some(request),
+                    Err(err) => {
+                        return Err(format!(
+                            "Something went wrong while building a Discord request, error: {err}"
+                        ));
+                    }
+                }
+            }
+            DiscordRequests::DeleteFollowup((application_id, interaction_token, message_id)) => {
+                match Request::builder(&Route::DeleteWebhookMessage {
+                    message_id,
+                    thread_id: None,
+                    token: &interaction_token,
+                    webhook_id: application_id,
+                })
+                .build()
+                {
+                    Ok(request) => Some(request),
+                    Err(err) => {
+                        return Err(format!(
+                            "Something went wrong while building a Discord request, error: {err}"
+                        ));
+                    }
+                }
+            }
+            DiscordRequests::RawHttp((method, path, contents)) => {
+                Some(raw_http::build_request(&method, &path, contents)?)
+            }
         };
 
-        if let Some(request) = request {
-            match self.http_client.request::<Vec<u8>>(request).await {
-                Ok(response) => Ok(Some(response.bytes().await.unwrap().clone())),
-                Err(err) => Err(format!(
-                    "Something went wrong while making a Discord request, error: {err}"
-                )),
+        Ok(request)
+    }
+
+    /// Sends the presence to every shard and stores it so it can be reapplied when a shard
+    /// starts a new session.
+    pub async fn update_presence(&self, presence: UpdatePresencePayload) {
+        if let Some(gateway) = self.gateway.get() {
+            for shard_message_sender in gateway.shard_message_senders.values() {
+                let _ = shard_message_sender.command(&UpdatePresence {
+                    d: presence.clone(),
+                    op: OpCode::PresenceUpdate,
+                });
             }
-        } else {
-            Ok(None)
         }
+
+        *self.presence.write().await = Some(presence);
     }
 }
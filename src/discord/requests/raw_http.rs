@@ -0,0 +1,415 @@
+/* SPDX-License-Identifier: GPL-3.0-or-later */
+/* Copyright © 2026 Eduard Smet */
+
+use twilight_http::{
+    request::{Method, Request, RequestBuilder},
+    routing::Path,
+};
+use twilight_model::id::{
+    Id,
+    marker::{ChannelMarker, GuildMarker},
+};
+
+use crate::plugins::discord_bot::plugin::discord_types::Contents;
+
+/// The Discord REST routes plugins can reach through raw HTTP requests, `{id}` segments only
+/// match snowflakes and `{}` segments match any value (like emojis and interaction tokens).
+static ROUTE_TEMPLATES: &[(Method, &str)] = &[
+    // Channels
+    (Method::Get, "channels/{id}"),
+    (Method::Patch, "channels/{id}"),
+    (Method::Delete, "channels/{id}"),
+    (Method::Put, "channels/{id}/permissions/{id}"),
+    (Method::Delete, "channels/{id}/permissions/{id}"),
+    (Method::Get, "channels/{id}/invites"),
+    (Method::Post, "channels/{id}/invites"),
+    (Method::Post, "channels/{id}/followers"),
+    (Method::Post, "channels/{id}/typing"),
+    (Method::Get, "channels/{id}/webhooks"),
+    (Method::Post, "channels/{id}/webhooks"),
+    // Messages
+    (Method::Get, "channels/{id}/messages"),
+    (Method::Post, "channels/{id}/messages"),
+    (Method::Post, "channels/{id}/messages/bulk-delete"),
+    (Method::Get, "channels/{id}/messages/{id}"),
+    (Method::Patch, "channels/{id}/messages/{id}"),
+    (Method::Delete, "channels/{id}/messages/{id}"),
+    (Method::Post, "channels/{id}/messages/{id}/crosspost"),
+    (Method::Delete, "channels/{id}/messages/{id}/reactions"),
+    (Method::Get, "channels/{id}/messages/{id}/reactions/{}"),
+    (Method::Delete, "channels/{id}/messages/{id}/reactions/{}"),
+    (Method::Put, "channels/{id}/messages/{id}/reactions/{}/@me"),
+    (
+        Method::Delete,
+        "channels/{id}/messages/{id}/reactions/{}/@me",
+    ),
+    (
+        Method::Delete,
+        "channels/{id}/messages/{id}/reactions/{}/{id}",
+    ),
+    (Method::Get, "channels/{id}/messages/pins"),
+    (Method::Put, "channels/{id}/messages/pins/{id}"),
+    (Method::Delete, "channels/{id}/messages/pins/{id}"),
+    (Method::Get, "channels/{id}/polls/{id}/answers/{}"),
+    (Method::Post, "channels/{id}/polls/{id}/expire"),
+    // Threads
+    (Method::Post, "channels/{id}/threads"),
+    (Method::Post, "channels/{id}/messages/{id}/threads"),
+    (Method::Get, "channels/{id}/thread-members"),
+    (Method::Get, "channels/{id}/thread-members/{id}"),
+    (Method::Put, "channels/{id}/thread-members/{id}"),
+    (Method::Delete, "channels/{id}/thread-members/{id}"),
+    (Method::Put, "channels/{id}/thread-members/@me"),
+    (Method::Delete, "channels/{id}/thread-members/@me"),
+    (Method::Get, "channels/{id}/threads/archived/public"),
+    (Method::Get, "channels/{id}/threads/archived/private"),
+    (
+        Method::Get,
+        "channels/{id}/users/@me/threads/archived/private",
+    ),
+    // Guilds
+    (Method::Get, "guilds/{id}"),
+    (Method::Patch, "guilds/{id}"),
+    (Method::Get, "guilds/{id}/audit-logs"),
+    (Method::Get, "guilds/{id}/channels"),
+    (Method::Post, "guilds/{id}/channels"),
+    (Method::Patch, "guilds/{id}/channels"),
+    (Method::Get, "guilds/{id}/threads/active"),
+    (Method::Get, "guilds/{id}/invites"),
+    (Method::Get, "guilds/{id}/webhooks"),
+    (Method::Get, "guilds/{id}/bans"),
+    (Method::Get, "guilds/{id}/bans/{id}"),
+    (Method::Put, "guilds/{id}/bans/{id}"),
+    (Method::Delete, "guilds/{id}/bans/{id}"),
+    (Method::Post, "guilds/{id}/bulk-ban"),
+    (Method::Get, "guilds/{id}/emojis"),
+    (Method::Post, "guilds/{id}/emojis"),
+    (Method::Get, "guilds/{id}/emojis/{id}"),
+    (Method::Patch, "guilds/{id}/emojis/{id}"),
+    (Method::Delete, "guilds/{id}/emojis/{id}"),
+    (Method::Get, "guilds/{id}/stickers"),
+    (Method::Get, "guilds/{id}/stickers/{id}"),
+    (Method::Get, "guilds/{id}/scheduled-events"),
+    (Method::Post, "guilds/{id}/scheduled-events"),
+    (Method::Get, "guilds/{id}/scheduled-events/{id}"),
+    (Method::Patch, "guilds/{id}/scheduled-events/{id}"),
+    (Method::Delete, "guilds/{id}/scheduled-events/{id}"),
+    // Members
+    (Method::Get, "guilds/{id}/members"),
+    (Method::Get, "guilds/{id}/members/search"),
+    (Method::Get, "guilds/{id}/members/{id}"),
+    (Method::Patch, "guilds/{id}/members/{id}"),
+    (Method::Delete, "guilds/{id}/members/{id}"),
+    (Method::Patch, "guilds/{id}/members/@me"),
+    (Method::Put, "guilds/{id}/members/{id}/roles/{id}"),
+    (Method::Delete, "guilds/{id}/members/{id}/roles/{id}"),
+    // Roles
+    (Method::Get, "guilds/{id}/roles"),
+    (Method::Post, "guilds/{id}/roles"),
+    (Method::Patch, "guilds/{id}/roles"),
+    (Method::Get, "guilds/{id}/roles/{id}"),
+    (Method::Patch, "guilds/{id}/roles/{id}"),
+    (Method::Delete, "guilds/{id}/roles/{id}"),
+    // Users
+    (Method::Get, "users/@me"),
+    (Method::Get, "users/{id}"),
+    (Method::Post, "users/@me/channels"),
+    (Method::Get, "users/@me/guilds"),
+    (Method::Delete, "users/@me/guilds/{id}"),
+    // Invites, stickers and webhooks
+    (Method::Get, "invites/{}"),
+    (Method::Delete, "invites/{}"),
+    (Method::Get, "stickers/{id}"),
+    (Method::Get, "webhooks/{id}"),
+    (Method::Patch, "webhooks/{id}"),
+    (Method::Delete, "webhooks/{id}"),
+];
+
+/// Allowlisted routes addressing resources the plugin scopes can not attribute to a guild or
+/// channel, plugins with scopes get denied them.
+static UNSCOPED_ROUTE_TEMPLATES: &[(Method, &str)] = &[
+    // The channel to follow into is part of the body
+    (Method::Post, "channels/{id}/followers"),
+    (Method::Post, "users/@me/channels"),
+    (Method::Delete, "invites/{}"),
+    (Method::Get, "webhooks/{id}"),
+    // A webhook can be moved into any channel through the body
+    (Method::Patch, "webhooks/{id}"),
+    (Method::Delete, "webhooks/{id}"),
+];
+
+fn parse_method(method: &str) -> Option<Method> {
+    match method.to_uppercase().as_str() {
+        "DELETE" => Some(Method::Delete),
+        "GET" => Some(Method::Get),
+        "PATCH" => Some(Method::Patch),
+        "POST" => Some(Method::Post),
+        "PUT" => Some(Method::Put),
+        _ => None,
+    }
+}
+
+fn route_matches(template: &str, path: &str) -> bool {
+    let mut template_segments = template.split('/');
+    let mut path_segments = path.split('/');
+
+    loop {
+        match (template_segments.next(), path_segments.next()) {
+            (None, None) => return true,
+            (Some("{id}"), Some(segment)) => {
+                if segment.is_empty() || !segment.bytes().all(|byte| byte.is_ascii_digit()) {
+                    return false;
+                }
+            }
+            (Some("{}"), Some(segment)) => {
+                if !valid_segment(segment) {
+                    return false;
+                }
+            }
+            (Some(template_segment), Some(segment)) => {
+                if template_segment != segment {
+                    return false;
+                }
+            }
+            _ => return false,
+        }
+    }
+}
+
+/// Rejects empty and dot segments and encoded separators, which could escape the matched route.
+fn valid_segment(segment: &str) -> bool {
+    let segment = segment.to_ascii_lowercase();
+
+    if segment.contains(['\\', '#']) || segment.contains("%2f") || segment.contains("%5c") {
+        return false;
+    }
+
+    !matches!(segment.replace("%2e", ".").as_str(), "" | "." | "..")
+}
+
+/// Query parameters are `key=value` pairs with a snake case key and an unreserved or percent
+/// encoded value.
+fn valid_query(query: &str) -> bool {
+    query.split('&').all(|parameter| {
+        let (key, value) = parameter.split_once('=').unwrap_or((parameter, ""));
+
+        !key.is_empty()
+            && key
+                .bytes()
+                .all(|byte| byte.is_ascii_lowercase() || byte == b'_')
+            && value.bytes().all(|byte| {
+                byte.is_ascii_alphanumeric()
+                    || matches!(byte, b'-' | b'.' | b'_' | b'~' | b'%' | b',')
+            })
+    })
+}
+
+/// Splits a raw HTTP path into its route and query, leading slashes and the API version prefix
+/// are optional.
+fn normalize_path(path: &str) -> (&str, Option<&str>) {
+    let path = path.trim_start_matches('/');
+    let path = path.strip_prefix("api/").unwrap_or(path);
+    let path = path
+        .strip_prefix("v10/")
+        .unwrap_or(path)
+        .trim_end_matches('/');
+
+    match path.split_once('?') {
+        Some((route, query)) => (route.trim_end_matches('/'), Some(query)),
+        None => (path, None),
+    }
+}
+
+/// Builds a request for any allowlisted Discord REST route, it is sent through the same HTTP
+/// client as the other requests so the rate limiter applies.
+pub fn build_request(
+    method: &str,
+    path: &str,
+    contents: Option<Contents>,
+) -> Result<Request, String> {
+    let Some(method) = parse_method(method) else {
+        return Err(format!("The {method} HTTP method is not supported"));
+    };
+
+    let (route, query) = normalize_path(path);
+
+    if !ROUTE_TEMPLATES.iter().any(|(template_method, template)| {
+        *template_method == method && route_matches(template, route)
+    }) {
+        return Err(format!(
+            "The {} {route} route is not allowed for raw HTTP requests",
+            method.name()
+        ));
+    }
+
+    if let Some(query) = query
+        && !valid_query(query)
+    {
+        return Err(format!(
+            "The {query} query is not allowed for raw HTTP requests"
+        ));
+    }
+
+    // The rate limit bucket gets derived from the route, like for the other requests
+    let ratelimit_path = match Path::try_from((method, route)) {
+        Ok(ratelimit_path) => ratelimit_path,
+        Err(err) => {
+            return Err(format!(
+                "Something went wrong while parsing the {route} route its rate limit path, error: {err}"
+            ));
+        }
+    };
+
+    let path_and_query = match query {
+        Some(query) => format!("{route}?{query}"),
+        None => String::from(route),
+    };
+
+    let request_builder = RequestBuilder::raw(method, ratelimit_path, path_and_query);
+
+    let request_builder = match contents {
+        Some(Contents::Json(bytes)) => request_builder.body(bytes),
+        Some(Contents::Form(buffer)) => match request_builder.multipart(buffer) {
+            Ok(request) => request,
+            Err(err) => {
+                return Err(err.to_string());
+            }
+        },
+        None => request_builder,
+    };
+
+    match request_builder.build() {
+        Ok(request) => Ok(request),
+        Err(err) => Err(format!(
+            "Something went wrong while building a Discord request, error: {err}"
+        )),
+    }
+}
+
+/// The guild or channel a raw HTTP request addresses, used for the plugin scopes. None when
+/// the route can not be attributed to a guild or channel.
+pub fn target(
+    method: &str,
+    path: &str,
+) -> Option<(Option<Id<GuildMarker>>, Option<Id<ChannelMarker>>)> {
+    let method = parse_method(method)?;
+    let (route, _) = normalize_path(path);
+
+    if UNSCOPED_ROUTE_TEMPLATES
+        .iter()
+        .any(|(template_method, template)| {
+            *template_method == method && route_matches(template, route)
+        })
+    {
+        return None;
+    }
+
+    let mut segments = route.split('/');
+
+    match (
+        segments.next(),
+        segments.next(),
+        segments.next(),
+        segments.next(),
+    ) {
+        (Some("guilds"), Some(guild_id), _, _)
+        | (Some("users"), Some("@me"), Some("guilds"), Some(guild_id)) => {
+            Some((guild_id.parse().ok().and_then(Id::new_checked), None))
+        }
+        (Some("channels"), Some(channel_id), _, _) => {
+            Some((None, channel_id.parse().ok().and_then(Id::new_checked)))
+        }
+        _ => Some((None, None)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn route_matches_snowflake_segments() {
+        assert!(route_matches(
+            "channels/{id}/messages/{id}",
+            "channels/1/messages/2"
+        ));
+        assert!(!route_matches(
+            "channels/{id}/messages/{id}",
+            "channels/1/messages/a"
+        ));
+        assert!(!route_matches(
+            "channels/{id}/messages/{id}",
+            "channels//messages/2"
+        ));
+        assert!(!route_matches("channels/{id}", "channels/1/messages"));
+        assert!(!route_matches("channels/{id}/messages", "channels/1"));
+    }
+
+    #[test]
+    fn route_matches_rejects_escaping_segments() {
+        let template = "channels/{id}/messages/{id}/reactions/{}";
+
+        assert!(route_matches(
+            template,
+            "channels/1/messages/2/reactions/%F0%9F%91%8D"
+        ));
+        assert!(!route_matches(template, "channels/1/messages/2/reactions/"));
+        assert!(!route_matches(
+            template,
+            "channels/1/messages/2/reactions/."
+        ));
+        assert!(!route_matches(
+            template,
+            "channels/1/messages/2/reactions/.."
+        ));
+        assert!(!route_matches(
+            template,
+            "channels/1/messages/2/reactions/%2e%2E"
+        ));
+        assert!(!route_matches(
+            template,
+            "channels/1/messages/2/reactions/a%2Fb"
+        ));
+        assert!(!route_matches(
+            template,
+            "channels/1/messages/2/reactions/a%5cb"
+        ));
+    }
+
+    #[test]
+    fn valid_query_parameters() {
+        assert!(valid_query("limit=50&before=123"));
+        assert!(valid_query("with_counts=true"));
+        assert!(!valid_query("limit=50&"));
+        assert!(!valid_query("limit=5#0"));
+        assert!(!valid_query("limit=../x"));
+        assert!(!valid_query("Limit=50"));
+    }
+
+    #[test]
+    fn build_request_rejects_unlisted_routes() {
+        assert!(build_request("GET", "gateway/bot", None).is_err());
+        assert!(build_request("POST", "channels/1", None).is_err());
+        assert!(build_request("GET", "channels/1/messages?limit=5#0", None).is_err());
+    }
+
+    #[test]
+    fn target_resolves_scopes() {
+        assert_eq!(
+            target("GET", "/api/v10/guilds/1/members"),
+            Some((Id::new_checked(1), None))
+        );
+        assert_eq!(
+            target("POST", "channels/2/messages"),
+            Some((None, Id::new_checked(2)))
+        );
+        assert_eq!(
+            target("DELETE", "users/@me/guilds/3"),
+            Some((Id::new_checked(3), None))
+        );
+        assert_eq!(target("GET", "users/@me"), Some((None, None)));
+        assert_eq!(target("PATCH", "webhooks/4"), None);
+        assert_eq!(target("DELETE", "invites/abc"), None);
+        assert_eq!(target("POST", "channels/5/followers"), None);
+    }
+}
@@ -0,0 +1,145 @@
+/* SPDX-License-Identifier: GPL-3.0-or-later */
+/* Copyright © 2026 Eduard Smet */
+
+use twilight_gateway::Event;
+use twilight_model::id::{
+    Id,
+    marker::{ChannelMarker, GuildMarker},
+};
+
+use crate::{
+    discord::{DiscordBotClient, requests::raw_http},
+    plugins::discord_bot::plugin::host_functions::DiscordRequests,
+};
+
+impl DiscordBotClient {
+    pub fn event_channel_id(event: &Event) -> Option<Id<ChannelMarker>> {
+        match event {
+            Event::InteractionCreate(interaction_create) => interaction_create
+                .channel
+                .as_ref()
+                .map(|channel| channel.id),
+            Event::MessageCreate(message_create) => Some(message_create.channel_id),
+            Event::ThreadCreate(thread_create) => Some(thread_create.id),
+            Event::ThreadDelete(thread_delete) => Some(thread_delete.parent_id),
+            Event::ThreadMemberUpdate(thread_member_update) => thread_member_update.member.id,
+            Event::ThreadMembersUpdate(thread_members_update) => Some(thread_members_update.id),
+            Event::ThreadUpdate(thread_update) => Some(thread_update.id),
+            Event::MessageUpdate(message_update) => Some(message_update.channel_id),
+            Event::MessageDelete(message_delete) => Some(message_delete.channel_id),
+            Event::MessageDeleteBulk(message_delete_bulk) => Some(message_delete_bulk.channel_id),
+            Event::ReactionAdd(reaction_add) => Some(reaction_add.channel_id),
+            Event::ReactionRemove(reaction_remove) => Some(reaction_remove.channel_id),
+            Event::ReactionRemoveAll(reaction_remove_all) => Some(reaction_remove_all.channel_id),
+            Event::ReactionRemoveEmoji(reaction_remove_emoji) => {
+                Some(reaction_remove_emoji.channel_id)
+            }
+            Event::ChannelCreate(channel_create) => Some(channel_create.id),
+            Event::ChannelUpdate(channel_update) => Some(channel_update.id),
+            Event::ChannelDelete(channel_delete) => Some(channel_delete.id),
+            Event::VoiceStateUpdate(voice_state_update) => voice_state_update.channel_id,
+            Event::InviteCreate(invite_create) => Some(invite_create.channel_id),
+            Event::InviteDelete(invite_delete) => Some(invite_delete.channel_id),
+            _ => None,
+        }
+    }
+
+    /// Checks the guild and channel scopes of a plugin. The guild of a channel and the
+    /// parent of a thread get resolved through the cache, plugins with a guild scope get denied
+    /// channels whose guild is unknown.
+    pub fn plugin_scope_allows(
+        &self,
+        plugin_uid: &str,
+        guild_id: Option<Id<GuildMarker>>,
+        channel_id: Option<Id<ChannelMarker>>,
+    ) -> bool {
+        let Some(plugin_scopes) = self.plugin_scopes.get(plugin_uid) else {
+            return true;
+        };
+
+        let mut guild_id = guild_id;
+        let mut parent_id = None;
+
+        if let Some(channel_id) = channel_id
+            && let Some(gateway) = self.gateway.get()
+            && let Some(channel) = gateway.cache.channel(channel_id)
+        {
+            guild_id = guild_id.or(channel.guild_id);
+            parent_id = channel.parent_id;
+        }
+
+        if let Some(guilds) = &plugin_scopes.guilds {
+            match guild_id {
+                Some(guild_id) if !guilds.allows(guild_id) => return false,
+                // The guild of an uncached channel is unknown, it could be outside of the scope
+                None if channel_id.is_some() => return false,
+                _ => {}
+            }
+        }
+
+        if let Some(channels) = &plugin_scopes.channels
+            && let Some(channel_id) = channel_id
+            && !channels.allows(channel_id)
+            && parent_id.is_none_or(|parent_id| !channels.allows(parent_id))
+        {
+            return false;
+        }
+
+        true
+    }
+
+    pub fn plugin_scope_allows_request(&self, plugin_uid: &str, request: &DiscordRequests) -> bool {
+        let (guild_id, channel_id) = match request {
+            DiscordRequests::RequestGuildMembers((guild_id, _))
+            | DiscordRequests::UpdateVoiceState((guild_id, _))
+            | DiscordRequests::CreateBan((guild_id, _, _))
+            | DiscordRequests::GetActiveThreads(guild_id)
+            | DiscordRequests::UpdateMember((guild_id, _, _)) => (Id::new_checked(*guild_id), None),
+            DiscordRequests::RequestSoundboardSounds(guild_ids) => {
+                return guild_ids.iter().all(|guild_id| {
+                    self.plugin_scope_allows(plugin_uid, Id::new_checked(*guild_id), None)
+                });
+            }
+            DiscordRequests::AddThreadMember((channel_id, _))
+            | DiscordRequests::CreateForumThread((channel_id, _))
+            | DiscordRequests::CreateMessage((channel_id, _))
+            | DiscordRequests::CreateThread((channel_id, _))
+            | DiscordRequests::CreateThreadFromMessage((channel_id, _, _))
+            | DiscordRequests::DeleteMessage((channel_id, _))
+            | DiscordRequests::GetChannel(channel_id)
+            | DiscordRequests::GetJoinedPrivateArchivedThreads((_, channel_id, _))
+            | DiscordRequests::GetPrivateArchivedThreads((_, channel_id, _))
+            | DiscordRequests::GetPublicArchivedThreads((_, channel_id, _))
+            | DiscordRequests::GetThreadMember((channel_id, _))
+            | DiscordRequests::GetThreadMembers((_, channel_id, _, _))
+            | DiscordRequests::JoinThread(channel_id)
+            | DiscordRequests::LeaveThread(channel_id)
+            | DiscordRequests::RemoveThreadMember((channel_id, _)) => {
+                (None, Id::new_checked(*channel_id))
+            }
+            DiscordRequests::RawHttp((method, path, _)) => match raw_http::target(method, path) {
+                Some(target) => target,
+                None => {
+                    return self
+                        .plugin_scopes
+                        .get(plugin_uid)
+                        .is_none_or(|plugin_scopes| {
+                            plugin_scopes.guilds.is_none() && plugin_scopes.channels.is_none()
+                        });
+                }
+            },
+            DiscordRequests::UpdatePresence(_)
+            | DiscordRequests::InteractionCallback(_)
+            | DiscordRequests::AutocompleteResult(_)
+            | DiscordRequests::UpdateInteractionOriginal(_)
+            | DiscordRequests::GetInteractionOriginal(_)
+            | DiscordRequests::DeleteInteractionOriginal(_)
+            | DiscordRequests::CreateFollowup(_)
+            | DiscordRequests::GetFollowup(_)
+            | DiscordRequests::UpdateFollowup(_)
+            | DiscordRequests::DeleteFollowup(_) => (None, None),
+        };
+
+        self.plugin_scope_allows(plugin_uid, guild_id, channel_id)
+    }
+}
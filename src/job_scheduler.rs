@@ -145,3 +145,12 @@ impl JobScheduler {
         }))
     }
 }
+
+/// Checks a cron expression with the same parser the job scheduler uses, the crons come from
+/// the plugin registrations so they can only be checked once the plugins are initialized.
+pub fn validate_cron(cron: &str) -> Result<(), String> {
+    match Job::new_async_tz(cron, chrono::Local, |_uuid, _lock| Box::pin(async {})) {
+        Ok(_) => Ok(()),
+        Err(err) => Err(err.to_string()),
+    }
+}
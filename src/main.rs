@@ -14,7 +14,11 @@ use std::{
 };
 
 use clap::Parser;
-use tokio::{signal, sync::RwLock};
+use tokio::{
+    signal,
+    sync::{RwLock, mpsc::Receiver},
+    task::JoinHandle,
+};
 use tracing::{error, info, warn};
 use tracing_appender::non_blocking::WorkerGuard;
 
@@ -26,16 +30,17 @@ mod job_scheduler;
 mod plugins;
 mod utils;
 
-use cli::{Cli, CliLogParameters};
+use cli::{Cli, CliCommands, CliCommandsCommands, CliLogParameters};
 use config::Config;
-use discord::DiscordBotClient;
+use discord::{ConfigDiscordCommandConflicts, DiscordBotClient};
 use http::HttpClient;
 use job_scheduler::JobScheduler;
 use plugins::{
-    AvailablePlugin, PluginRegistrations, builder::PluginBuilder, registry, runtime::Runtime,
+    AvailablePlugin, PluginRegistrations, builder::PluginBuilder,
+    commands::PluginApplicationCommand, registry, runtime::Runtime,
 };
 
-use crate::utils::channels::Channels;
+use crate::utils::channels::{Channels, DiscordBotClientMessages};
 
 #[derive(PartialEq)]
 enum Shutdown {
@@ -52,19 +57,35 @@ async fn main() -> ExitCode {
 
     info!("Exiting the program");
 
-    if result.is_ok() {
-        match SHUTDOWN.read().await.as_ref().unwrap() {
-            Shutdown::Normal => return ExitCode::from(0),
-            Shutdown::SigInt => return ExitCode::from(130),
-            Shutdown::Restart => restart(),
-        }
+    if result.is_err() {
+        return ExitCode::from(1);
+    }
+
+    match SHUTDOWN.read().await.as_ref().unwrap() {
+        Shutdown::Normal => return ExitCode::from(0),
+        Shutdown::SigInt => return ExitCode::from(130),
+        Shutdown::Restart => restart(),
     }
 
     ExitCode::from(0)
 }
 
 async fn run() -> Result<(), ()> {
-    let cli = Cli::parse();
+    let mut cli = Cli::parse();
+
+    match cli.command.take() {
+        Some(CliCommands::Validate) => {
+            return validate(cli.log_parameters, &cli.config_file).await;
+        }
+        Some(CliCommands::Commands {
+            command: CliCommandsCommands::List,
+        }) => return list_commands(cli).await,
+        Some(CliCommands::Commands {
+            command: CliCommandsCommands::Sync { dry_run },
+        }) => return sync_commands(cli, dry_run).await,
+        None => {}
+    }
+
     //let mut tasks: Arc<Mutex<Vec<JoinHandle<()>>>> = Arc::new(Mutex::new(vec![])); // TODO: Rework shutdown
 
     let (_guard, discord_bot_client_token, channels) =
@@ -72,6 +93,9 @@ async fn run() -> Result<(), ()> {
 
     let config = Config::new(&cli.config_file)?;
 
+    let config_discord = config.discord.clone();
+    let command_conflicts = config.discord.command_conflicts;
+
     let available_plugins = registry_get_plugins(
         cli.http_client_timeout_seconds,
         config,
@@ -82,14 +106,6 @@ async fn run() -> Result<(), ()> {
 
     let plugin_registrations = Arc::new(RwLock::new(PluginRegistrations::new()));
 
-    let (discord_bot_client, shards) = DiscordBotClient::new(
-        discord_bot_client_token,
-        plugin_registrations.clone(),
-        channels.runtime.discord_bot_client_sender,
-        channels.discord_bot_client.receiver,
-    )
-    .await?;
-
     info!("Creating the job scheduler");
     let job_scheduler = JobScheduler::new(
         plugin_registrations.clone(),
@@ -105,23 +121,214 @@ async fn run() -> Result<(), ()> {
         channels.runtime.receiver,
     ));
 
-    discord_bot_client.start(shards);
+    let discord_bot_client = Arc::new(DiscordBotClient::new(
+        discord_bot_client_token.clone(),
+        config_discord.clone(),
+        &available_plugins,
+        plugin_registrations.clone(),
+        channels.runtime.discord_bot_client_sender,
+        channels.discord_bot_client.receiver,
+    ));
+
+    DiscordBotClient::start(discord_bot_client.clone());
 
     job_scheduler.start().await?;
 
     plugin_initializations(
         runtime.clone(),
-        available_plugins,
+        available_plugins.clone(),
         plugin_registrations,
+        command_conflicts,
         &cli.plugin_directory,
     )
     .await?;
 
+    // The gateway intents and cache depend on the registrations, so the gateway only gets
+    // connected after the plugin initialization
+    DiscordBotClient::connect(
+        discord_bot_client,
+        discord_bot_client_token,
+        &config_discord,
+        &available_plugins,
+    )
+    .await?;
+
     Runtime::start(runtime.clone());
 
     shutdown(runtime).await
 }
 
+async fn validate(cli_log_parameters: CliLogParameters, config_file: &Path) -> Result<(), ()> {
+    let _guard = utils::logger::new(cli_log_parameters)?;
+
+    Config::new(config_file)?;
+
+    info!("The config file is valid");
+
+    *SHUTDOWN.write().await = Some(Shutdown::Normal);
+
+    Ok(())
+}
+
+async fn list_commands(cli: Cli) -> Result<(), ()> {
+    let _guard = utils::logger::new(cli.log_parameters)?;
+
+    utils::env::load_env_file(&cli.env_file)?;
+
+    let config = Config::new(&cli.config_file)?;
+
+    let commands = plugin_application_commands(
+        config,
+        cli.plugin_directory,
+        cli.http_client_timeout_seconds,
+        cli.cache,
+    )
+    .await?;
+
+    for command in commands {
+        let scope = match command.command.guild_id {
+            Some(guild_id) => format!("guild {guild_id}"),
+            None => String::from("global"),
+        };
+
+        let label = discord::interactions::application_command_label(&command.command);
+
+        if command.command.name == command.requested_name {
+            println!("{}\t{}\t{}", &label, &command.plugin_id, &scope);
+        } else {
+            println!(
+                "{}\t{}\t{}\t(requested as {})",
+                &label, &command.plugin_id, &scope, &command.requested_name
+            );
+        }
+    }
+
+    *SHUTDOWN.write().await = Some(Shutdown::Normal);
+
+    Ok(())
+}
+
+async fn sync_commands(cli: Cli, dry_run: bool) -> Result<(), ()> {
+    let _guard = utils::logger::new(cli.log_parameters)?;
+
+    utils::env::load_env_file(&cli.env_file)?;
+
+    let discord_bot_client_token = utils::env::validate()?;
+
+    let config = Config::new(&cli.config_file)?;
+
+    let keep_unknown_commands = config.discord.keep_unknown_commands;
+    let sweep_guild_commands = config.discord.sweep_guild_commands;
+
+    let commands = plugin_application_commands(
+        config,
+        cli.plugin_directory,
+        cli.http_client_timeout_seconds,
+        cli.cache,
+    )
+    .await?;
+
+    let diffs = discord::interactions::sync_application_commands(
+        discord_bot_client_token,
+        &commands,
+        keep_unknown_commands,
+        sweep_guild_commands,
+        dry_run,
+    )
+    .await?;
+
+    let changes = diffs
+        .iter()
+        .flat_map(|diff| diff.changes())
+        .collect::<Vec<_>>();
+
+    if changes.is_empty() {
+        println!("The application commands are up to date");
+    }
+
+    for change in changes {
+        println!("{change}");
+    }
+
+    *SHUTDOWN.write().await = Some(Shutdown::Normal);
+
+    Ok(())
+}
+
+/// Initializes the plugins without connecting to Discord and returns the application commands
+/// they register, Discord requests made during the initialization get refused.
+async fn plugin_application_commands(
+    config: Config,
+    plugin_directory: PathBuf,
+    http_client_timeout_seconds: u64,
+    cache: bool,
+) -> Result<Vec<PluginApplicationCommand>, ()> {
+    let command_conflicts = config.discord.command_conflicts;
+
+    let available_plugins = registry_get_plugins(
+        http_client_timeout_seconds,
+        config,
+        plugin_directory.clone(),
+        cache,
+    )
+    .await?;
+
+    let channels = utils::channels::new();
+
+    let runtime = Arc::new(Runtime::new(
+        channels.discord_bot_client.sender,
+        channels.job_scheduler.sender,
+        channels.runtime.receiver,
+    ));
+
+    let discord_bot_client_task = refuse_discord_requests(channels.discord_bot_client.receiver);
+
+    plugin_initializations(
+        runtime,
+        available_plugins,
+        Arc::new(RwLock::new(PluginRegistrations::new())),
+        command_conflicts,
+        &plugin_directory,
+    )
+    .await?;
+
+    match discord_bot_client_task.await {
+        Ok(commands) => Ok(commands),
+        Err(err) => {
+            error!("Something went wrong while collecting the application commands, error: {err}");
+            Err(())
+        }
+    }
+}
+
+/// Refuses the Discord requests made while the plugins get initialized without connecting to
+/// Discord, until they registered their application commands which get returned.
+fn refuse_discord_requests(
+    mut discord_bot_client_rx: Receiver<DiscordBotClientMessages>,
+) -> JoinHandle<Vec<PluginApplicationCommand>> {
+    tokio::spawn(async move {
+        while let Some(message) = discord_bot_client_rx.recv().await {
+            match message {
+                DiscordBotClientMessages::RegisterApplicationCommands(commands) => {
+                    return commands;
+                }
+                DiscordBotClientMessages::Request(_, _, response_sender) => {
+                    let _ =
+                        response_sender.send(Err(String::from("Not connected to Discord").into()));
+                }
+                DiscordBotClientMessages::CacheRequest(_, _, response_sender) => {
+                    let _ = response_sender.send(Err(String::from("Not connected to Discord")));
+                }
+                DiscordBotClientMessages::Shutdown(is_done) => {
+                    let _ = is_done.send(());
+                }
+            }
+        }
+
+        vec![]
+    })
+}
+
 fn initialization(
     cli_log_parameters: CliLogParameters,
     env_file: &Path,
@@ -152,6 +359,7 @@ async fn plugin_initializations(
     runtime: Arc<Runtime>,
     available_plugins: HashMap<String, AvailablePlugin>,
     plugin_registrations: Arc<RwLock<PluginRegistrations>>,
+    command_conflicts: ConfigDiscordCommandConflicts,
     config_directory: &Path,
 ) -> Result<(), ()> {
     info!("Creating the WASI plugin builder");
@@ -163,6 +371,7 @@ async fn plugin_initializations(
         plugin_builder,
         available_plugins,
         plugin_registrations,
+        command_conflicts,
         config_directory,
     )
     .await
@@ -2,43 +2,354 @@
 /* Copyright © 2026 Eduard Smet */
 
 pub mod builder;
+pub mod commands;
+pub mod custom_ids;
 pub mod registry;
 pub mod runtime;
+pub mod settings;
 
-use std::collections::{HashMap, HashSet};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+    num::{NonZeroU64, NonZeroUsize},
+};
 
 use semver::Version;
-use serde::{Deserialize, Deserializer};
+use serde::{
+    Deserialize, Deserializer,
+    de::{
+        self, MapAccess, SeqAccess, Visitor,
+        value::{MapAccessDeserializer, SeqAccessDeserializer},
+    },
+};
 use serde_yaml_ng::Value;
-use twilight_model::id::{Id, marker::CommandMarker};
+use twilight_gateway::EventType;
+use twilight_model::id::{
+    Id,
+    marker::{ChannelMarker, CommandMarker, GuildMarker},
+};
 
-use crate::plugins::discord_bot::plugin::plugin_types::SupportedRegistrations;
+use crate::{
+    discord::events::{self, REGISTRABLE_EVENT_TYPES},
+    plugins::{
+        custom_ids::CustomIdRoutes,
+        discord_bot::plugin::{
+            discord_types::Requests as DiscordRequests, plugin_types::SupportedRegistrations,
+        },
+    },
+};
 
 wasmtime::component::bindgen!({ imports: { default: async }, exports: { default: async } });
 
 #[derive(Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct ConfigPlugin {
+    #[serde(deserialize_with = "registry::deserialize_plugin_reference")]
     pub plugin: String,
     pub cache: Option<bool>,
-    #[serde(default = "ConfigPlugin::permissions_default")]
-    pub permissions: SupportedRegistrations,
+    #[serde(default)]
+    pub permissions: ConfigPluginPermissions,
     pub environment: Option<HashMap<String, String>>,
     pub settings: Option<Value>,
+    pub network: Option<ConfigPluginNetwork>,
+    pub guilds: Option<ConfigPluginScope<GuildMarker>>,
+    pub channels: Option<ConfigPluginScope<ChannelMarker>>,
+    #[serde(default, deserialize_with = "deserialize_custom_id_namespace")]
+    pub custom_id_namespace: Option<String>,
+    pub commands: Option<ConfigPluginCommands>,
 }
 
-impl ConfigPlugin {
-    fn permissions_default() -> SupportedRegistrations {
+/// rename: command names mapped to the name they get registered as.
+/// aliases: command names mapped to additional names they get registered under.
+/// disable: command names which do not get registered.
+/// register: overrides the `guild_id` of the commands, registering them globally or into the
+/// listed guilds instead.
+/// defer: command names mapped to the automatic deferral of their interactions.
+#[derive(Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ConfigPluginCommands {
+    #[serde(default)]
+    pub rename: HashMap<String, String>,
+    #[serde(default)]
+    pub aliases: HashMap<String, Vec<String>>,
+    #[serde(default)]
+    pub disable: HashSet<String>,
+    pub register: Option<ConfigPluginCommandsRegister>,
+    #[serde(default)]
+    pub defer: HashMap<String, ConfigPluginCommandsDefer>,
+}
+
+/// `threshold_ms`: how long the plugin gets to respond before the host sends a deferred
+/// response, 2000 when omitted as interactions expire after 3 seconds.
+/// ephemeral: makes the deferred response, and with that the final response, ephemeral.
+#[derive(Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ConfigPluginCommandsDefer {
+    pub threshold_ms: Option<u64>,
+    #[serde(default)]
+    pub ephemeral: bool,
+}
+
+#[derive(Clone, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConfigPluginCommandsRegister {
+    Global,
+    #[serde(deserialize_with = "deserialize_register_guilds")]
+    Guilds(Vec<Id<GuildMarker>>),
+}
+
+/// An empty list of guilds would silently stop registering the commands of the plugin.
+fn deserialize_register_guilds<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Vec<Id<GuildMarker>>, D::Error> {
+    let guild_ids = Vec::<Id<GuildMarker>>::deserialize(deserializer)?;
+
+    if guild_ids.is_empty() {
+        return Err(de::Error::invalid_length(0, &"at least one guild ID"));
+    }
+
+    Ok(guild_ids)
+}
+
+/// Opts the plugin into custom ID namespacing, the host prefixes the custom IDs of the
+/// components and modals the plugin sends with the namespace and strips it again from
+/// incoming interactions.
+fn deserialize_custom_id_namespace<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<String>, D::Error> {
+    let custom_id_namespace = String::deserialize(deserializer)?;
+
+    if custom_id_namespace.is_empty()
+        || custom_id_namespace.len() > 16
+        || custom_id_namespace.contains(':')
+    {
+        return Err(de::Error::invalid_value(
+            de::Unexpected::Str(&custom_id_namespace),
+            &"a namespace of 1 to 16 characters without colons",
+        ));
+    }
+
+    Ok(Some(custom_id_namespace))
+}
+
+/// allow: host names the plugin may send HTTP requests to, a leading `*.` matches any
+/// subdomain. When omitted every host is allowed.
+#[derive(Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ConfigPluginNetwork {
+    pub allow: Option<Vec<String>>,
+    pub rate_limit: Option<ConfigPluginNetworkRateLimit>,
+}
+
+#[derive(Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ConfigPluginNetworkRateLimit {
+    pub requests: NonZeroUsize,
+    pub seconds: NonZeroU64,
+}
+
+/// Either a list of registrations or a map containing the registrations, the
+/// `discord_requests` the plugin is allowed to make and whether it can read the `discord_cache`.
+/// When `discord_requests` is omitted every request except `raw-http` is allowed.
+#[derive(Clone)]
+pub struct ConfigPluginPermissions {
+    pub registrations: SupportedRegistrations,
+    pub discord_events: HashSet<EventType>,
+    pub discord_requests: Option<HashSet<String>>,
+    pub discord_cache: bool,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ConfigPluginPermissionsMap {
+    #[serde(default = "ConfigPluginPermissions::registrations_default")]
+    registrations: ConfigPluginRegistrations,
+    #[serde(
+        default,
+        deserialize_with = "ConfigPluginPermissions::deserialize_discord_requests"
+    )]
+    discord_requests: Option<HashSet<String>>,
+    #[serde(default)]
+    discord_cache: bool,
+}
+
+/// The registrations and the Discord gateway events (`DISCORD_EVENT_` followed by the event
+/// name) from a list of permission strings.
+struct ConfigPluginRegistrations(SupportedRegistrations, HashSet<EventType>);
+
+impl Default for ConfigPluginPermissions {
+    fn default() -> Self {
+        let registrations = Self::registrations_default();
+
+        ConfigPluginPermissions {
+            registrations: registrations.0,
+            discord_events: registrations.1,
+            discord_requests: None,
+            discord_cache: false,
+        }
+    }
+}
+
+/// allow: the IDs the plugin is limited to, when omitted every ID is allowed.
+/// deny: the IDs the plugin is never allowed to receive events from or address.
+///
+/// Events and requests which do not relate to a guild or channel are not affected.
+#[derive(Clone, Deserialize)]
+#[serde(bound = "", deny_unknown_fields)]
+pub struct ConfigPluginScope<T> {
+    pub allow: Option<HashSet<Id<T>>>,
+    #[serde(default)]
+    pub deny: HashSet<Id<T>>,
+}
+
+impl<T> ConfigPluginScope<T> {
+    pub fn allows(&self, id: Id<T>) -> bool {
+        !self.deny.contains(&id) && self.allow.as_ref().is_none_or(|allow| allow.contains(&id))
+    }
+}
+
+impl ConfigPluginPermissions {
+    fn registrations_default() -> ConfigPluginRegistrations {
         let mut supported_registrations = SupportedRegistrations::all();
 
         supported_registrations &= !SupportedRegistrations::SHUTDOWN;
 
-        supported_registrations
+        let mut supported_discord_events = HashSet::from([EventType::InteractionCreate]);
+
+        supported_discord_events.extend(REGISTRABLE_EVENT_TYPES);
+
+        ConfigPluginRegistrations(supported_registrations, supported_discord_events)
+    }
+
+    fn deserialize_discord_requests<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<HashSet<String>>, D::Error> {
+        let mut discord_requests = HashSet::new();
+
+        for discord_request in Vec::<String>::deserialize(deserializer)? {
+            let discord_request = discord_request.to_lowercase().replace('_', "-");
+
+            if !DISCORD_REQUEST_NAMES.contains(&discord_request.as_str()) {
+                return Err(de::Error::unknown_variant(
+                    &discord_request,
+                    DISCORD_REQUEST_NAMES,
+                ));
+            }
+
+            discord_requests.insert(discord_request);
+        }
+
+        Ok(Some(discord_requests))
+    }
+
+    pub fn allows_discord_request(&self, request: &DiscordRequests) -> bool {
+        match &self.discord_requests {
+            Some(discord_requests) => discord_requests.contains(request.name()),
+            None => !OPT_IN_DISCORD_REQUEST_NAMES.contains(&request.name()),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for ConfigPluginPermissions {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct ConfigPluginPermissionsVisitor;
+
+        impl<'de> Visitor<'de> for ConfigPluginPermissionsVisitor {
+            type Value = ConfigPluginPermissions;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("a list of registrations or a permissions map")
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, seq: A) -> Result<Self::Value, A::Error> {
+                let registrations =
+                    ConfigPluginRegistrations::deserialize(SeqAccessDeserializer::new(seq))?;
+
+                Ok(ConfigPluginPermissions {
+                    registrations: registrations.0,
+                    discord_events: registrations.1,
+                    discord_requests: None,
+                    discord_cache: false,
+                })
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, map: A) -> Result<Self::Value, A::Error> {
+                let permissions_map =
+                    ConfigPluginPermissionsMap::deserialize(MapAccessDeserializer::new(map))?;
+
+                Ok(ConfigPluginPermissions {
+                    registrations: permissions_map.registrations.0,
+                    discord_events: permissions_map.registrations.1,
+                    discord_requests: permissions_map.discord_requests,
+                    discord_cache: permissions_map.discord_cache,
+                })
+            }
+        }
+
+        deserializer.deserialize_any(ConfigPluginPermissionsVisitor)
     }
 }
 
-impl<'de> Deserialize<'de> for SupportedRegistrations {
+/// Requests which are not allowed by omitting `discord_requests`, they have to be listed
+/// explicitly.
+static OPT_IN_DISCORD_REQUEST_NAMES: &[&str] = &["raw-http"];
+
+/// Generates the list of Discord request names plugins can get permission for and the function
+/// which names a request, so the two can not drift apart.
+macro_rules! discord_request_names {
+    ($($request:ident => $name:literal),* $(,)?) => {
+        static DISCORD_REQUEST_NAMES: &[&str] = &[$($name),*];
+
+        impl DiscordRequests {
+            /// The WIT name of the request variant, as used in the plugin permissions.
+            pub fn name(&self) -> &'static str {
+                match self {
+                    $(DiscordRequests::$request(_) => $name,)*
+                }
+            }
+        }
+    };
+}
+
+discord_request_names![
+    RequestGuildMembers => "request-guild-members",
+    RequestSoundboardSounds => "request-soundboard-sounds",
+    UpdateVoiceState => "update-voice-state",
+    UpdatePresence => "update-presence",
+    AddThreadMember => "add-thread-member",
+    CreateBan => "create-ban",
+    CreateForumThread => "create-forum-thread",
+    CreateMessage => "create-message",
+    CreateThread => "create-thread",
+    CreateThreadFromMessage => "create-thread-from-message",
+    DeleteMessage => "delete-message",
+    GetActiveThreads => "get-active-threads",
+    GetChannel => "get-channel",
+    GetJoinedPrivateArchivedThreads => "get-joined-private-archived-threads",
+    GetPrivateArchivedThreads => "get-private-archived-threads",
+    GetPublicArchivedThreads => "get-public-archived-threads",
+    GetThreadMember => "get-thread-member",
+    GetThreadMembers => "get-thread-members",
+    InteractionCallback => "interaction-callback",
+    JoinThread => "join-thread",
+    LeaveThread => "leave-thread",
+    RemoveThreadMember => "remove-thread-member",
+    UpdateMember => "update-member",
+    UpdateInteractionOriginal => "update-interaction-original",
+    AutocompleteResult => "autocomplete-result",
+    GetInteractionOriginal => "get-interaction-original",
+    DeleteInteractionOriginal => "delete-interaction-original",
+    CreateFollowup => "create-followup",
+    GetFollowup => "get-followup",
+    UpdateFollowup => "update-followup",
+    DeleteFollowup => "delete-followup",
+    RawHttp => "raw-http",
+];
+
+impl<'de> Deserialize<'de> for ConfigPluginRegistrations {
     fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
         let mut supported_registrations = SupportedRegistrations::empty();
+        let mut supported_discord_events = HashSet::new();
 
         let supported_registration_strings = Vec::<String>::deserialize(deserializer)?;
 
@@ -47,52 +358,74 @@ impl<'de> Deserialize<'de> for SupportedRegistrations {
                 "DEPENDENCY_FUNCTIONS" => {
                     supported_registrations |= SupportedRegistrations::DEPENDENCY_FUNCTIONS;
                 }
-                "DISCORD_EVENT_MESSAGE_CREATE" => {
-                    supported_registrations |= SupportedRegistrations::DISCORD_EVENT_MESSAGE_CREATE;
-                }
-                "DISCORD_EVENT_INTERACTION_CREATE" => {
-                    supported_registrations |=
-                        SupportedRegistrations::DISCORD_EVENT_INTERACTION_CREATE;
-                }
-                "DISCORD_EVENT_THREAD_CREATE" => {
-                    supported_registrations |= SupportedRegistrations::DISCORD_EVENT_THREAD_CREATE;
-                }
-                "DISCORD_EVENT_THREAD_DELETE" => {
-                    supported_registrations |= SupportedRegistrations::DISCORD_EVENT_THREAD_DELETE;
-                }
-                "DISCORD_EVENT_THREAD_LIST_SYNC" => {
-                    supported_registrations |=
-                        SupportedRegistrations::DISCORD_EVENT_THREAD_LIST_SYNC;
-                }
-                "DISCORD_EVENT_THREAD_MEMBER_UPDATE" => {
-                    supported_registrations |=
-                        SupportedRegistrations::DISCORD_EVENT_THREAD_MEMBER_UPDATE;
-                }
-                "DISCORD_EVENT_THREAD_MEMBERS_UPDATE" => {
-                    supported_registrations |=
-                        SupportedRegistrations::DISCORD_EVENT_THREAD_MEMBERS_UPDATE;
-                }
-                "DISCORD_EVENT_THREAD_UPDATE" => {
-                    supported_registrations |= SupportedRegistrations::DISCORD_EVENT_THREAD_UPDATE;
+                "SCHEDULED_JOBS" => {
+                    supported_registrations |= SupportedRegistrations::SCHEDULED_JOBS;
                 }
                 "SHUTDOWN" => {
                     supported_registrations |= SupportedRegistrations::SHUTDOWN;
                 }
-                &_ => unimplemented!(),
+                other => {
+                    let Some(event_type) = other
+                        .strip_prefix("DISCORD_EVENT_")
+                        .and_then(events::permissible_event_type)
+                    else {
+                        return Err(de::Error::custom(format!(
+                            "unknown permission `{supported_registration_string}`, expected DEPENDENCY_FUNCTIONS, SCHEDULED_JOBS, SHUTDOWN or DISCORD_EVENT_ followed by one of INTERACTION_CREATE, {}",
+                            REGISTRABLE_EVENT_TYPES
+                                .iter()
+                                .filter_map(|event_type| event_type.name())
+                                .collect::<Vec<_>>()
+                                .join(", ")
+                        )));
+                    };
+
+                    supported_discord_events.insert(event_type);
+                }
             }
         }
 
-        Ok(supported_registrations)
+        Ok(ConfigPluginRegistrations(
+            supported_registrations,
+            supported_discord_events,
+        ))
     }
 }
 
+#[derive(Clone)]
 pub struct AvailablePlugin {
     pub registry_id: String,
     pub id: String,
     pub version: Version,
-    pub permissions: SupportedRegistrations,
+    pub permissions: ConfigPluginPermissions,
     pub environment: Option<HashMap<String, String>>,
     pub settings: Option<Value>,
+    pub network: Option<ConfigPluginNetwork>,
+    pub guilds: Option<ConfigPluginScope<GuildMarker>>,
+    pub channels: Option<ConfigPluginScope<ChannelMarker>>,
+    pub custom_id_namespace: Option<String>,
+    pub commands: Option<ConfigPluginCommands>,
+}
+
+pub struct PluginScopes {
+    pub guilds: Option<ConfigPluginScope<GuildMarker>>,
+    pub channels: Option<ConfigPluginScope<ChannelMarker>>,
+}
+
+impl PluginScopes {
+    pub fn new(available_plugins: &HashMap<String, AvailablePlugin>) -> HashMap<String, Self> {
+        available_plugins
+            .iter()
+            .map(|(plugin_uid, available_plugin)| {
+                (
+                    plugin_uid.clone(),
+                    PluginScopes {
+                        guilds: available_plugin.guilds.clone(),
+                        channels: available_plugin.channels.clone(),
+                    },
+                )
+            })
+            .collect()
+    }
 }
 
 // TODO: Plugins which did not register anything should get dropped
@@ -104,19 +437,13 @@ pub struct PluginRegistrations {
 
 pub struct PluginRegistrationsDiscordEvents {
     pub interaction_create: PluginRegistrationsInteractionCreate,
-    pub message_create: Vec<String>,
-    pub thread_create: Vec<String>,
-    pub thread_delete: Vec<String>,
-    pub thread_list_sync: Vec<String>,
-    pub thread_member_update: Vec<String>,
-    pub thread_members_update: Vec<String>,
-    pub thread_update: Vec<String>,
+    pub events: HashMap<EventType, Vec<String>>, // Event type, plugin IDs
 }
 
 pub struct PluginRegistrationsInteractionCreate {
-    pub application_commands: HashMap<Id<CommandMarker>, String>, // Command ID, plugin ID
-    pub message_components: HashMap<String, String>, // Message Component ID, plugin ID ISSUE: ID overlap is possible
-    pub modals: HashMap<String, String>, // Modal ID, plugin ID ISSUE: ID overlap is possible
+    pub application_commands: HashMap<Id<CommandMarker>, (String, String)>, // Command ID, (plugin ID, requested name)
+    pub message_components: CustomIdRoutes,
+    pub modals: CustomIdRoutes,
 }
 
 pub struct PluginRegistrationRequests {
@@ -161,16 +488,10 @@ impl PluginRegistrations {
             discord_events: PluginRegistrationsDiscordEvents {
                 interaction_create: PluginRegistrationsInteractionCreate {
                     application_commands: HashMap::new(),
-                    message_components: HashMap::new(),
-                    modals: HashMap::new(),
+                    message_components: CustomIdRoutes::new("message component"),
+                    modals: CustomIdRoutes::new("modal"),
                 },
-                message_create: vec![],
-                thread_create: vec![],
-                thread_delete: vec![],
-                thread_list_sync: vec![],
-                thread_member_update: vec![],
-                thread_members_update: vec![],
-                thread_update: vec![],
+                events: HashMap::new(),
             },
             scheduled_jobs: HashMap::new(),
             dependency_functions: HashMap::new(),
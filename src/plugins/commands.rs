@@ -0,0 +1,224 @@
+/* SPDX-License-Identifier: GPL-3.0-or-later */
+/* Copyright © 2026 Eduard Smet */
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use tracing::{error, info, warn};
+use twilight_model::{
+    application::command::{Command, CommandType},
+    id::{Id, marker::GuildMarker},
+};
+
+use crate::{
+    discord::ConfigDiscordCommandConflicts,
+    plugins::{
+        ConfigPluginCommands, ConfigPluginCommandsRegister,
+        PluginRegistrationRequestsApplicationCommand,
+    },
+};
+
+/// An application command with its final name, `requested_name` is the name the plugin used.
+pub struct PluginApplicationCommand {
+    pub plugin_id: String,
+    pub requested_name: String,
+    pub command: Command,
+}
+
+/// Applies the disabled commands, renames and aliases from the config and resolves the
+/// remaining name conflicts between plugins.
+pub fn resolve(
+    requests: Vec<PluginRegistrationRequestsApplicationCommand>,
+    plugin_commands: &HashMap<String, ConfigPluginCommands>,
+    command_conflicts: ConfigDiscordCommandConflicts,
+) -> Result<Vec<PluginApplicationCommand>, ()> {
+    // Names only have to be unique per command type within the same guild or globally
+    let mut commands_by_name =
+        BTreeMap::<(u8, Option<Id<GuildMarker>>, String), Vec<PluginApplicationCommand>>::new();
+
+    for request in requests {
+        let mut command = match sonic_rs::from_slice::<Command>(&request.data) {
+            Ok(command) => command,
+            Err(err) => {
+                error!(
+                    "Something went wrong while deserializing a command from the {} plugin requested to register, error: {}",
+                    &request.plugin_id, &err
+                );
+                continue;
+            }
+        };
+
+        let requested_name = command.name.clone();
+
+        // Discord rejects context menu commands with a description or options, which would fail
+        // the bulk overwrite of every command in their scope
+        if matches!(command.kind, CommandType::User | CommandType::Message)
+            && (!command.description.is_empty() || !command.options.is_empty())
+        {
+            warn!(
+                "The {} context menu command of the {} plugin has a description or options, they get dropped",
+                &requested_name, &request.plugin_id
+            );
+
+            command.description.clear();
+            command.options.clear();
+        }
+
+        let Some((names, guild_ids)) = configured_registrations(
+            &request.plugin_id,
+            &requested_name,
+            command.guild_id,
+            plugin_commands.get(&request.plugin_id),
+        ) else {
+            continue;
+        };
+
+        for name in names {
+            command.name = name;
+
+            for guild_id in &guild_ids {
+                command.guild_id = *guild_id;
+
+                commands_by_name
+                    .entry((
+                        u8::from(command.kind),
+                        command.guild_id,
+                        command.name.clone(),
+                    ))
+                    .or_default()
+                    .push(PluginApplicationCommand {
+                        plugin_id: request.plugin_id.clone(),
+                        requested_name: requested_name.clone(),
+                        command: command.clone(),
+                    });
+            }
+        }
+    }
+
+    let mut conflicts = false;
+    let mut commands = vec![];
+
+    for ((_, guild_id, name), mut commands_with_name) in commands_by_name {
+        if resolve_conflict(&name, guild_id, &mut commands_with_name, command_conflicts).is_err() {
+            conflicts = true;
+            continue;
+        }
+
+        commands.extend(commands_with_name);
+    }
+
+    if conflicts {
+        return Err(());
+    }
+
+    for command in &commands {
+        if command.command.name == command.requested_name {
+            info!(
+                "The {} command of the {} plugin is registered as is",
+                &command.requested_name, &command.plugin_id
+            );
+        } else {
+            info!(
+                "The {} command of the {} plugin is registered as {}",
+                &command.requested_name, &command.plugin_id, &command.command.name
+            );
+        }
+    }
+
+    Ok(commands)
+}
+
+/// The names and guilds a command gets registered under after applying the config, `None`
+/// when the command is disabled.
+fn configured_registrations(
+    plugin_id: &str,
+    requested_name: &str,
+    guild_id: Option<Id<GuildMarker>>,
+    config_plugin_commands: Option<&ConfigPluginCommands>,
+) -> Option<(Vec<String>, Vec<Option<Id<GuildMarker>>>)> {
+    let mut names = vec![requested_name.to_string()];
+    let mut guild_ids = vec![guild_id];
+
+    let Some(config_plugin_commands) = config_plugin_commands else {
+        return Some((names, guild_ids));
+    };
+
+    if config_plugin_commands.disable.contains(requested_name) {
+        info!("The {requested_name} command of the {plugin_id} plugin is disabled in the config");
+        return None;
+    }
+
+    if let Some(rename) = config_plugin_commands.rename.get(requested_name) {
+        names[0].clone_from(rename);
+    }
+
+    if let Some(aliases) = config_plugin_commands.aliases.get(requested_name) {
+        names.extend(aliases.iter().cloned());
+    }
+
+    match &config_plugin_commands.register {
+        Some(ConfigPluginCommandsRegister::Global) => guild_ids = vec![None],
+        Some(ConfigPluginCommandsRegister::Guilds(config_guild_ids)) => {
+            guild_ids = config_guild_ids.iter().copied().map(Some).collect();
+        }
+        None => {}
+    }
+
+    Some((names, guild_ids))
+}
+
+/// Fails or suffixes the names of commands with the same name, type and guild registered by
+/// multiple plugins, depending on the config.
+fn resolve_conflict(
+    name: &str,
+    guild_id: Option<Id<GuildMarker>>,
+    commands_with_name: &mut [PluginApplicationCommand],
+    command_conflicts: ConfigDiscordCommandConflicts,
+) -> Result<(), ()> {
+    // A plugin registering the same command multiple times is not a conflict
+    let owners = commands_with_name
+        .iter()
+        .map(|command| (command.plugin_id.clone(), command.requested_name.clone()))
+        .collect::<BTreeSet<_>>();
+
+    if owners.len() < 2 {
+        return Ok(());
+    }
+
+    let plugins = owners
+        .iter()
+        .map(|(plugin_id, _)| plugin_id.as_str())
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    let scope = match guild_id {
+        Some(guild_id) => format!("guild {guild_id}"),
+        None => String::from("global"),
+    };
+
+    match command_conflicts {
+        ConfigDiscordCommandConflicts::Fail => {
+            error!(
+                "The {name} command ({scope}) is registered by multiple plugins: {plugins}, rename or disable it in the config"
+            );
+            Err(())
+        }
+        ConfigDiscordCommandConflicts::Rename => {
+            warn!(
+                "The {name} command ({scope}) is registered by multiple plugins: {plugins}, suffixing their names with ~N"
+            );
+
+            for command in commands_with_name {
+                let index = owners
+                    .iter()
+                    .position(|(plugin_id, requested_name)| {
+                        *plugin_id == command.plugin_id && *requested_name == command.requested_name
+                    })
+                    .unwrap();
+
+                command.command.name = format!("{name}~{}", index + 1);
+            }
+
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,238 @@
+/* SPDX-License-Identifier: GPL-3.0-or-later */
+/* Copyright © 2026 Eduard Smet */
+
+use tracing::{error, warn};
+
+/// Routes message component and modal custom IDs to plugins. Registrations are glob patterns,
+/// `*` matches any sequence and `?` any single character, so `poll:vote:*` covers every
+/// custom ID starting with `poll:vote:`. A registration without wildcards is an exact match.
+pub struct CustomIdRoutes {
+    kind: &'static str,
+    routes: Vec<CustomIdRoute>,
+}
+
+struct CustomIdRoute {
+    pattern: Vec<char>,
+    specificity: usize,
+    plugin_uid: String,
+}
+
+impl CustomIdRoutes {
+    pub fn new(kind: &'static str) -> Self {
+        CustomIdRoutes {
+            kind,
+            routes: vec![],
+        }
+    }
+
+    /// Registrations of other plugins which match the same custom IDs with the same
+    /// specificity are ambiguous and get rejected, other overlaps are resolved by the longest
+    /// match.
+    pub fn insert(&mut self, plugin_uid: &str, pattern: &str) -> Result<(), ()> {
+        let pattern = pattern.chars().collect::<Vec<char>>();
+        let specificity = pattern
+            .iter()
+            .filter(|character| !matches!(character, '*' | '?'))
+            .count();
+
+        for route in &self.routes {
+            if !Self::patterns_overlap(&route.pattern, &pattern) {
+                continue;
+            }
+
+            if route.plugin_uid == plugin_uid {
+                if route.pattern == pattern {
+                    return Ok(());
+                }
+
+                continue;
+            }
+
+            if route.specificity == specificity {
+                error!(
+                    "The {} {} ID registration of the {} plugin overlaps with the {} registration of the {} plugin, skipping it",
+                    String::from_iter(&pattern),
+                    self.kind,
+                    plugin_uid,
+                    String::from_iter(&route.pattern),
+                    &route.plugin_uid
+                );
+                return Err(());
+            }
+
+            warn!(
+                "The {} {} ID registration of the {} plugin overlaps with the {} registration of the {} plugin, the longest match receives the interaction",
+                String::from_iter(&pattern),
+                self.kind,
+                plugin_uid,
+                String::from_iter(&route.pattern),
+                &route.plugin_uid
+            );
+        }
+
+        self.routes.push(CustomIdRoute {
+            pattern,
+            specificity,
+            plugin_uid: plugin_uid.to_string(),
+        });
+
+        Ok(())
+    }
+
+    /// The plugin of the most specific registration matching the custom ID.
+    pub fn get(&self, custom_id: &str) -> Option<&String> {
+        let custom_id = custom_id.chars().collect::<Vec<char>>();
+
+        self.routes
+            .iter()
+            .filter(|route| Self::pattern_matches(&route.pattern, &custom_id))
+            .fold(None, |best: Option<&CustomIdRoute>, route| match best {
+                Some(best) if best.specificity >= route.specificity => Some(best),
+                _ => Some(route),
+            })
+            .map(|route| &route.plugin_uid)
+    }
+
+    fn pattern_matches(pattern: &[char], custom_id: &[char]) -> bool {
+        fn matches(
+            pattern: &[char],
+            custom_id: &[char],
+            memo: &mut Vec<Option<bool>>,
+            width: usize,
+        ) -> bool {
+            let key = pattern.len() * width + custom_id.len();
+
+            if let Some(result) = memo[key] {
+                return result;
+            }
+
+            let result = match pattern.split_first() {
+                None => custom_id.is_empty(),
+                Some(('*', rest)) => {
+                    matches(rest, custom_id, memo, width)
+                        || (!custom_id.is_empty() && matches(pattern, &custom_id[1..], memo, width))
+                }
+                Some(('?', rest)) => {
+                    !custom_id.is_empty() && matches(rest, &custom_id[1..], memo, width)
+                }
+                Some((character, rest)) => {
+                    custom_id.first() == Some(character)
+                        && matches(rest, &custom_id[1..], memo, width)
+                }
+            };
+
+            memo[key] = Some(result);
+
+            result
+        }
+
+        let width = custom_id.len() + 1;
+
+        matches(
+            pattern,
+            custom_id,
+            &mut vec![None; (pattern.len() + 1) * width],
+            width,
+        )
+    }
+
+    /// Whether a custom ID exists which matches both patterns.
+    fn patterns_overlap(a: &[char], b: &[char]) -> bool {
+        fn overlap(a: &[char], b: &[char], memo: &mut Vec<Option<bool>>, width: usize) -> bool {
+            let key = a.len() * width + b.len();
+
+            if let Some(result) = memo[key] {
+                return result;
+            }
+
+            let result = match (a.first(), b.first()) {
+                (None, None) => true,
+                (Some('*'), _) => {
+                    overlap(&a[1..], b, memo, width)
+                        || (!b.is_empty() && overlap(a, &b[1..], memo, width))
+                }
+                (_, Some('*')) => {
+                    overlap(a, &b[1..], memo, width)
+                        || (!a.is_empty() && overlap(&a[1..], b, memo, width))
+                }
+                (Some(a_character), Some(b_character)) => {
+                    (*a_character == '?' || *b_character == '?' || a_character == b_character)
+                        && overlap(&a[1..], &b[1..], memo, width)
+                }
+                _ => false,
+            };
+
+            memo[key] = Some(result);
+
+            result
+        }
+
+        let width = b.len() + 1;
+
+        overlap(a, b, &mut vec![None; (a.len() + 1) * width], width)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chars(value: &str) -> Vec<char> {
+        value.chars().collect()
+    }
+
+    #[test]
+    fn pattern_matches_wildcards() {
+        let matches = |pattern: &str, custom_id: &str| {
+            CustomIdRoutes::pattern_matches(&chars(pattern), &chars(custom_id))
+        };
+
+        assert!(matches("poll:vote", "poll:vote"));
+        assert!(!matches("poll:vote", "poll:votes"));
+        assert!(matches("poll:vote:*", "poll:vote:"));
+        assert!(matches("poll:vote:*", "poll:vote:1:2"));
+        assert!(!matches("poll:vote:*", "poll:close:1"));
+        assert!(matches("poll:?", "poll:1"));
+        assert!(!matches("poll:?", "poll:"));
+        assert!(!matches("poll:?", "poll:12"));
+        assert!(matches("*:*:*", "a:b:c"));
+        assert!(!matches("*a*a*a*a*b", &"a".repeat(100)));
+    }
+
+    #[test]
+    fn patterns_overlap_wildcards() {
+        let overlap = |a: &str, b: &str| CustomIdRoutes::patterns_overlap(&chars(a), &chars(b));
+
+        assert!(overlap("poll:*", "poll:vote:*"));
+        assert!(overlap("poll:vote:*", "*:vote:1"));
+        assert!(overlap("poll:?", "poll:1"));
+        assert!(overlap("*", ""));
+        assert!(!overlap("poll:*", "vote:*"));
+        assert!(!overlap("poll:?", "poll:12"));
+        assert!(!overlap("poll:vote", "poll:votes"));
+    }
+
+    #[test]
+    fn equally_specific_overlaps_are_rejected() {
+        let mut routes = CustomIdRoutes::new("message component");
+
+        assert!(routes.insert("a", "poll:*:1").is_ok());
+        assert!(routes.insert("b", "poll:1:*").is_err());
+        assert!(routes.insert("a", "poll:1:*").is_ok());
+        assert!(routes.insert("a", "poll:*:1").is_ok());
+    }
+
+    #[test]
+    fn most_specific_route_wins() {
+        let mut routes = CustomIdRoutes::new("message component");
+
+        assert!(routes.insert("a", "poll:*").is_ok());
+        assert!(routes.insert("b", "poll:vote:*").is_ok());
+        assert!(routes.insert("c", "poll:vote:1").is_ok());
+
+        assert_eq!(routes.get("poll:close").map(String::as_str), Some("a"));
+        assert_eq!(routes.get("poll:vote:2").map(String::as_str), Some("b"));
+        assert_eq!(routes.get("poll:vote:1").map(String::as_str), Some("c"));
+        assert_eq!(routes.get("vote"), None);
+    }
+}
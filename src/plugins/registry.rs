@@ -10,7 +10,10 @@ use std::{
 
 use anyhow::{Error, Result};
 use semver::{Version, VersionReq};
-use serde::Deserialize;
+use serde::{
+    Deserialize, Deserializer,
+    de::{self, Unexpected},
+};
 use tokio::fs;
 use tracing::{error, info, warn};
 
@@ -121,6 +124,11 @@ async fn get_cached_plugins(
                                 permissions: plugin_options.permissions,
                                 environment: plugin_options.environment,
                                 settings: plugin_options.settings,
+                                network: plugin_options.network,
+                                guilds: plugin_options.guilds,
+                                channels: plugin_options.channels,
+                                custom_id_namespace: plugin_options.custom_id_namespace,
+                                commands: plugin_options.commands,
                             },
                         );
 
@@ -210,6 +218,11 @@ async fn fetch_non_cached_plugins(
                             permissions: plugin_options.permissions,
                             environment: plugin_options.environment,
                             settings: plugin_options.settings,
+                            network: plugin_options.network,
+                            guilds: plugin_options.guilds,
+                            channels: plugin_options.channels,
+                            custom_id_namespace: plugin_options.custom_id_namespace,
+                            commands: plugin_options.commands,
                         },
                     ))
                 }));
@@ -241,6 +254,32 @@ async fn fetch_non_cached_plugins(
     }
 }
 
+pub fn deserialize_plugin_reference<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<String, D::Error> {
+    let value = String::deserialize(deserializer)?;
+
+    let (plugin_string, plugin_requested_version) = parse_plugin_string_requested_version(&value);
+    let (registry_id, plugin_id) = parse_plugin_string_registry_id(plugin_string);
+
+    if registry_id.is_empty() || plugin_id.is_empty() || value.contains(char::is_whitespace) {
+        return Err(de::Error::invalid_value(
+            Unexpected::Str(&value),
+            &"a plugin reference in the [REGISTRY/]PLUGIN[:VERSION] format",
+        ));
+    }
+
+    if plugin_requested_version != "latest"
+        && let Err(err) = Version::parse(plugin_requested_version)
+    {
+        return Err(de::Error::custom(format!(
+            "the requested version of the {plugin_id} plugin is not a valid semantic version or \"latest\": {err}"
+        )));
+    }
+
+    Ok(value)
+}
+
 fn parse_plugin_string_registry_id(value: &str) -> (&str, &str) {
     match value.rsplit_once('/') {
         Some((registry_id, plugin_string)) => (registry_id, plugin_string),
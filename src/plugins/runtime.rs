@@ -2,6 +2,7 @@
 /* Copyright © 2026 Eduard Smet */
 
 pub mod internal;
+pub mod network;
 
 use std::{
     collections::{HashMap, HashSet},
@@ -10,26 +11,31 @@ use std::{
     sync::Arc,
 };
 
-use serde_yaml_ng::Value;
 use tokio::sync::{
     Mutex, RwLock,
     mpsc::{Receiver, Sender},
     oneshot,
 };
 use tokio_util::sync::CancellationToken;
-use tracing::{error, info};
+use tracing::{error, info, warn};
+use twilight_gateway::EventType;
 use wasmtime::{Store, component::Component};
 use wasmtime_wasi::{DirPerms, FilePerms, ResourceTable, WasiCtxBuilder};
 use wasmtime_wasi_http::WasiHttpCtx;
 
 use crate::{
     SHUTDOWN, Shutdown,
+    discord::{ConfigDiscordCommandConflicts, events::REGISTRABLE_EVENT_TYPES},
+    job_scheduler,
     plugins::{
         AvailablePlugin, Plugin, PluginRegistrationRequests,
         PluginRegistrationRequestsApplicationCommand, PluginRegistrationRequestsScheduledJob,
-        PluginRegistrations, builder::PluginBuilder,
-        discord_bot::plugin::discord_types::Events as DiscordEvents,
-        runtime::internal::InternalRuntime,
+        PluginRegistrations,
+        builder::PluginBuilder,
+        commands,
+        discord_bot::plugin::discord_types::Event as DiscordEvent,
+        runtime::{internal::InternalRuntime, network::NetworkPolicy},
+        settings,
     },
     utils::channels::{DiscordBotClientMessages, JobSchedulerMessages, RuntimeMessages},
 };
@@ -90,8 +96,24 @@ impl Runtime {
         plugin_builder: PluginBuilder,
         plugins: HashMap<String, AvailablePlugin>,
         plugin_registrations: Arc<RwLock<PluginRegistrations>>,
+        command_conflicts: ConfigDiscordCommandConflicts,
         directory: &Path,
     ) -> Result<(), ()> {
+        let plugin_commands = plugins
+            .iter()
+            .filter_map(|(plugin_uid, plugin)| {
+                plugin
+                    .commands
+                    .clone()
+                    .map(|config_plugin_commands| (plugin_uid.clone(), config_plugin_commands))
+            })
+            .collect::<HashMap<_, _>>();
+
+        let custom_id_namespaces = plugins
+            .values()
+            .filter_map(|plugin| plugin.custom_id_namespace.clone())
+            .collect::<Vec<String>>();
+
         let mut registration_requests = PluginRegistrationRequests {
             discord_event_interaction_create: super::PluginRegistrationRequestsInteractionCreate {
                 application_commands: vec![],
@@ -107,6 +129,11 @@ impl Runtime {
                 .join(&plugin.id)
                 .join(plugin.version.to_string());
 
+            let Ok(settings) = settings::validate(&plugin_uid, &plugin_directory, plugin.settings)
+            else {
+                continue;
+            };
+
             let bytes = match fs::read(plugin_directory.join("plugin.wasm")) {
                 Ok(bytes) => bytes,
                 Err(err) => {
@@ -162,6 +189,9 @@ impl Runtime {
                 .unwrap()
                 .build();
 
+            let supported_registrations = plugin.permissions.registrations;
+            let supported_discord_events = plugin.permissions.discord_events.clone();
+
             let mut store = Store::<InternalRuntime>::new(
                 &plugin_builder.engine,
                 InternalRuntime::new(
@@ -169,6 +199,8 @@ impl Runtime {
                     wasi,
                     WasiHttpCtx::new(),
                     ResourceTable::new(),
+                    NetworkPolicy::new(plugin.network),
+                    plugin.permissions,
                     Arc::downgrade(&runtime),
                 ),
             );
@@ -191,8 +223,13 @@ impl Runtime {
                 .discord_bot_plugin_plugin_functions()
                 .call_initialization(
                     &mut store,
-                    &sonic_rs::to_vec(&plugin.settings.unwrap_or(Value::default())).unwrap(),
-                    plugin.permissions,
+                    &sonic_rs::to_vec(&settings).unwrap(),
+                    supported_registrations,
+                    &supported_discord_events
+                        .iter()
+                        .filter_map(EventType::name)
+                        .map(String::from)
+                        .collect::<Vec<String>>(),
                 )
                 .await
             {
@@ -221,70 +258,41 @@ impl Runtime {
             };
 
             if let Some(discord_events) = plugin_registrations_request.discord_events {
-                if discord_events.message_create {
-                    plugin_registrations
-                        .write()
-                        .await
-                        .discord_events
-                        .message_create
-                        .push(plugin_uid.clone());
-                }
-
-                if discord_events.thread_create {
-                    plugin_registrations
-                        .write()
-                        .await
-                        .discord_events
-                        .thread_create
-                        .push(plugin_uid.clone());
-                }
-
-                if discord_events.thread_delete {
-                    plugin_registrations
-                        .write()
-                        .await
-                        .discord_events
-                        .thread_delete
-                        .push(plugin_uid.clone());
-                }
-
-                if discord_events.thread_list_sync {
-                    plugin_registrations
-                        .write()
-                        .await
-                        .discord_events
-                        .thread_list_sync
-                        .push(plugin_uid.clone());
-                }
-
-                if discord_events.thread_member_update {
-                    plugin_registrations
-                        .write()
-                        .await
-                        .discord_events
-                        .thread_member_update
-                        .push(plugin_uid.clone());
-                }
-
-                if discord_events.thread_members_update {
-                    plugin_registrations
-                        .write()
-                        .await
-                        .discord_events
-                        .thread_members_update
-                        .push(plugin_uid.clone());
-                }
+                if let Some(events) = discord_events.events {
+                    for event in events {
+                        let Some(event_type) = EventType::try_from(event.as_str())
+                            .ok()
+                            .filter(|event_type| REGISTRABLE_EVENT_TYPES.contains(event_type))
+                        else {
+                            warn!(
+                                "The {} plugin tried to register an unknown Discord event: {}",
+                                &plugin_uid, &event
+                            );
+                            continue;
+                        };
+
+                        if !supported_discord_events.contains(&event_type) {
+                            warn!(
+                                "The {} plugin tried to register the {} Discord event without permission",
+                                &plugin_uid, &event
+                            );
+                            continue;
+                        }
 
-                if discord_events.thread_update {
-                    plugin_registrations
-                        .write()
-                        .await
-                        .discord_events
-                        .thread_update
-                        .push(plugin_uid.clone());
+                        plugin_registrations
+                            .write()
+                            .await
+                            .discord_events
+                            .events
+                            .entry(event_type)
+                            .or_default()
+                            .push(plugin_uid.clone());
+                    }
                 }
 
-                if let Some(interaction_create) = discord_events.interaction_create {
+                if let Some(interaction_create) = discord_events.interaction_create
+                    && supported_discord_events.contains(&EventType::InteractionCreate)
+                {
                     if let Some(application_commands) = interaction_create.application_commands {
                         for application_command in application_commands {
                             registration_requests
@@ -297,31 +305,51 @@ impl Runtime {
                         }
                     }
 
-                    if let Some(message_components) = interaction_create.message_components {
-                        // TODO: Prevent duplicate entries
-
+                    // Namespaced custom IDs are routed by their namespace instead
+                    if let Some(message_components) = interaction_create.message_components
+                        && plugin.custom_id_namespace.is_none()
+                    {
                         for message_component in message_components {
-                            plugin_registrations
+                            if let Some(namespace) = reserved_custom_id_namespace(
+                                &custom_id_namespaces,
+                                &message_component,
+                            ) {
+                                error!(
+                                    "The {message_component} message component ID registration of the {plugin_uid} plugin starts with the {namespace} custom ID namespace of another plugin, skipping it"
+                                );
+                                continue;
+                            }
+
+                            let _ = plugin_registrations
                                 .write()
                                 .await
                                 .discord_events
                                 .interaction_create
                                 .message_components
-                                .insert(message_component.clone(), plugin_uid.clone());
+                                .insert(&plugin_uid, &message_component);
                         }
                     }
 
-                    if let Some(modals) = interaction_create.modals {
-                        // TODO: Prevent duplicate entries
-
+                    if let Some(modals) = interaction_create.modals
+                        && plugin.custom_id_namespace.is_none()
+                    {
                         for modal in modals {
-                            plugin_registrations
+                            if let Some(namespace) =
+                                reserved_custom_id_namespace(&custom_id_namespaces, &modal)
+                            {
+                                error!(
+                                    "The {modal} modal ID registration of the {plugin_uid} plugin starts with the {namespace} custom ID namespace of another plugin, skipping it"
+                                );
+                                continue;
+                            }
+
+                            let _ = plugin_registrations
                                 .write()
                                 .await
                                 .discord_events
                                 .interaction_create
                                 .modals
-                                .insert(modal.clone(), plugin_uid.clone());
+                                .insert(&plugin_uid, &modal);
                         }
                     }
                 }
@@ -329,11 +357,26 @@ impl Runtime {
 
             if let Some(scheduled_jobs) = plugin_registrations_request.scheduled_jobs {
                 for scheduled_job in scheduled_jobs {
+                    let crons = scheduled_job
+                        .1
+                        .into_iter()
+                        .filter(|cron| match job_scheduler::validate_cron(cron) {
+                            Ok(()) => true,
+                            Err(err) => {
+                                error!(
+                                    "The {cron} cron expression of the {} scheduled job from the {plugin_uid} plugin is invalid, skipping it, error: {err}",
+                                    &scheduled_job.0
+                                );
+                                false
+                            }
+                        })
+                        .collect();
+
                     registration_requests.scheduled_jobs.push(
                         PluginRegistrationRequestsScheduledJob {
                             plugin_id: plugin_uid.clone(),
                             id: scheduled_job.0,
-                            crons: scheduled_job.1,
+                            crons,
                         },
                     );
                 }
@@ -358,12 +401,18 @@ impl Runtime {
                 .insert(plugin_uid, plugin_context);
         }
 
+        let application_commands = commands::resolve(
+            registration_requests
+                .discord_event_interaction_create
+                .application_commands,
+            &plugin_commands,
+            command_conflicts,
+        )?;
+
         let _ = runtime
             .discord_bot_client_tx
             .send(DiscordBotClientMessages::RegisterApplicationCommands(
-                registration_requests
-                    .discord_event_interaction_create
-                    .application_commands,
+                application_commands,
             ))
             .await;
 
@@ -379,7 +428,7 @@ impl Runtime {
 
     // TODO: Remove trapped plugins
 
-    async fn call_discord_event(&self, plugin_name: &str, event: &DiscordEvents) {
+    async fn call_discord_event(&self, plugin_name: &str, event: &DiscordEvent) {
         let plugins = self.plugins.read().await;
         let plugin = plugins.get(plugin_name).unwrap();
 
@@ -485,3 +534,16 @@ impl Runtime {
         self.cancellation_token.cancel();
     }
 }
+
+/// The namespace a custom ID registration starts with, namespaced custom IDs are routed to the
+/// plugin owning the namespace so such registrations would never receive an interaction.
+fn reserved_custom_id_namespace<'a>(
+    custom_id_namespaces: &'a [String],
+    pattern: &str,
+) -> Option<&'a String> {
+    custom_id_namespaces.iter().find(|namespace| {
+        pattern
+            .strip_prefix(namespace.as_str())
+            .is_some_and(|rest| rest.starts_with(':'))
+    })
+}
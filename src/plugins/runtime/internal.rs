@@ -6,20 +6,32 @@ use std::sync::Weak;
 use tokio::sync::oneshot;
 use tracing::{debug, error, info, trace, warn};
 use wasmtime_wasi::{ResourceTable, WasiCtx, WasiCtxView, WasiView};
-use wasmtime_wasi_http::{WasiHttpCtx, WasiHttpView};
+use wasmtime_wasi_http::{
+    HttpResult, WasiHttpCtx, WasiHttpView,
+    bindings::http::types::ErrorCode,
+    body::HyperOutgoingBody,
+    types::{HostFutureIncomingResponse, OutgoingRequestConfig, default_send_request},
+};
 
 use crate::{
     Shutdown,
+    discord::cache::DiscordCacheRequests,
     plugins::{
+        ConfigPluginPermissions,
         discord_bot::plugin::{
+            discord_cache::Host as DiscordCache,
             discord_types::{
-                Host as DiscordTypes, Requests as DiscordRequests, Responses as DiscordResponses,
+                Host as DiscordTypes, RequestError as DiscordRequestError,
+                Requests as DiscordRequests, Responses as DiscordResponses,
             },
             host_functions::Host as HostFunctions,
             host_types::{Host as HostTypes, LogLevels},
             plugin_types::Host as PluginTypes,
         },
-        runtime::Runtime,
+        runtime::{
+            Runtime,
+            network::{NetworkPolicy, NetworkPolicyDenial},
+        },
     },
     utils::channels::DiscordBotClientMessages,
 };
@@ -29,6 +41,8 @@ pub struct InternalRuntime {
     wasi: WasiCtx,
     wasi_http: WasiHttpCtx,
     table: ResourceTable,
+    network_policy: NetworkPolicy,
+    permissions: ConfigPluginPermissions,
     runtime: Weak<Runtime>,
 }
 
@@ -49,6 +63,32 @@ impl WasiHttpView for InternalRuntime {
     fn table(&mut self) -> &mut ResourceTable {
         &mut self.table
     }
+
+    fn send_request(
+        &mut self,
+        request: hyper::Request<HyperOutgoingBody>,
+        config: OutgoingRequestConfig,
+    ) -> HttpResult<HostFutureIncomingResponse> {
+        match self.network_policy.check(request.uri().host()) {
+            Ok(()) => Ok(default_send_request(request, config)),
+            Err(NetworkPolicyDenial::Host) => {
+                warn!(
+                    "The {} plugin tried to send an HTTP request to a host which is not allowed by its network policy: {}",
+                    &self.uid,
+                    request.uri()
+                );
+                Err(ErrorCode::HttpRequestDenied.into())
+            }
+            Err(NetworkPolicyDenial::RateLimit) => {
+                warn!(
+                    "The {} plugin exceeded the HTTP request rate limit of its network policy: {}",
+                    &self.uid,
+                    request.uri()
+                );
+                Err(ErrorCode::HttpRequestDenied.into())
+            }
+        }
+    }
 }
 
 impl HostFunctions for InternalRuntime {
@@ -65,14 +105,30 @@ impl HostFunctions for InternalRuntime {
     async fn discord_request(
         &mut self,
         request: DiscordRequests,
-    ) -> Result<Option<DiscordResponses>, String> {
+    ) -> Result<Option<DiscordResponses>, DiscordRequestError> {
+        if !self.permissions.allows_discord_request(&request) {
+            let err = format!(
+                "The {} plugin is not allowed to make {} Discord requests",
+                &self.uid,
+                request.name()
+            );
+
+            warn!(err);
+
+            return Err(err.into());
+        }
+
         let runtime = self.runtime.upgrade().unwrap();
 
         let (tx, rx) = oneshot::channel();
 
         if let Err(err) = runtime
             .discord_bot_client_tx
-            .send(DiscordBotClientMessages::Request(request, tx))
+            .send(DiscordBotClientMessages::Request(
+                self.uid.clone(),
+                request,
+                tx,
+            ))
             .await
         {
             let err = format!(
@@ -81,7 +137,7 @@ impl HostFunctions for InternalRuntime {
 
             error!(err);
 
-            return Err(err);
+            return Err(err.into());
         }
 
         match rx.await {
@@ -89,7 +145,7 @@ impl HostFunctions for InternalRuntime {
             Err(err) => {
                 let err = format!("The OneShot sender was dropped: {err}");
                 error!(err);
-                Err(err)
+                Err(err.into())
             }
         }
     }
@@ -145,6 +201,57 @@ impl HostFunctions for InternalRuntime {
     }
 }
 
+impl DiscordCache for InternalRuntime {
+    async fn get_guild(&mut self, guild_id: u64) -> Result<Option<Vec<u8>>, String> {
+        self.discord_cache_request(DiscordCacheRequests::Guild(guild_id))
+            .await
+    }
+
+    async fn get_channel(&mut self, channel_id: u64) -> Result<Option<Vec<u8>>, String> {
+        self.discord_cache_request(DiscordCacheRequests::Channel(channel_id))
+            .await
+    }
+
+    async fn get_member(&mut self, guild_id: u64, user_id: u64) -> Result<Option<Vec<u8>>, String> {
+        self.discord_cache_request(DiscordCacheRequests::Member(guild_id, user_id))
+            .await
+    }
+
+    async fn get_role(&mut self, role_id: u64) -> Result<Option<Vec<u8>>, String> {
+        self.discord_cache_request(DiscordCacheRequests::Role(role_id))
+            .await
+    }
+
+    async fn get_user(&mut self, user_id: u64) -> Result<Option<Vec<u8>>, String> {
+        self.discord_cache_request(DiscordCacheRequests::User(user_id))
+            .await
+    }
+
+    async fn get_voice_state(
+        &mut self,
+        guild_id: u64,
+        user_id: u64,
+    ) -> Result<Option<Vec<u8>>, String> {
+        self.discord_cache_request(DiscordCacheRequests::VoiceState(guild_id, user_id))
+            .await
+    }
+
+    async fn get_message(&mut self, message_id: u64) -> Result<Option<Vec<u8>>, String> {
+        self.discord_cache_request(DiscordCacheRequests::Message(message_id))
+            .await
+    }
+
+    async fn list_guild_members(&mut self, guild_id: u64) -> Result<Option<Vec<u8>>, String> {
+        self.discord_cache_request(DiscordCacheRequests::GuildMembers(guild_id))
+            .await
+    }
+
+    async fn list_guild_channels(&mut self, guild_id: u64) -> Result<Option<Vec<u8>>, String> {
+        self.discord_cache_request(DiscordCacheRequests::GuildChannels(guild_id))
+            .await
+    }
+}
+
 impl HostTypes for InternalRuntime {}
 impl PluginTypes for InternalRuntime {}
 impl DiscordTypes for InternalRuntime {}
@@ -155,6 +262,8 @@ impl InternalRuntime {
         wasi: WasiCtx,
         wasi_http: WasiHttpCtx,
         table: ResourceTable,
+        network_policy: NetworkPolicy,
+        permissions: ConfigPluginPermissions,
         runtime: Weak<Runtime>,
     ) -> Self {
         InternalRuntime {
@@ -162,7 +271,56 @@ impl InternalRuntime {
             wasi,
             wasi_http,
             table,
+            network_policy,
+            permissions,
             runtime,
         }
     }
+
+    async fn discord_cache_request(
+        &self,
+        request: DiscordCacheRequests,
+    ) -> Result<Option<Vec<u8>>, String> {
+        if !self.permissions.discord_cache {
+            let err = format!(
+                "The {} plugin is not allowed to read the Discord cache",
+                &self.uid
+            );
+
+            warn!(err);
+
+            return Err(err);
+        }
+
+        let runtime = self.runtime.upgrade().unwrap();
+
+        let (tx, rx) = oneshot::channel();
+
+        if let Err(err) = runtime
+            .discord_bot_client_tx
+            .send(DiscordBotClientMessages::CacheRequest(
+                self.uid.clone(),
+                request,
+                tx,
+            ))
+            .await
+        {
+            let err = format!(
+                "Something went wrong while sending a message over the Discord channel, error: {err}"
+            );
+
+            error!(err);
+
+            return Err(err);
+        }
+
+        match rx.await {
+            Ok(result) => result,
+            Err(err) => {
+                let err = format!("The OneShot sender was dropped: {err}");
+                error!(err);
+                Err(err)
+            }
+        }
+    }
 }
@@ -0,0 +1,194 @@
+/* SPDX-License-Identifier: GPL-3.0-or-later */
+/* Copyright © 2026 Eduard Smet */
+
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+use crate::plugins::ConfigPluginNetwork;
+
+pub struct NetworkPolicy {
+    allow: Option<Vec<String>>,
+    rate_limit: Option<NetworkPolicyRateLimit>,
+}
+
+struct NetworkPolicyRateLimit {
+    requests: usize,
+    window: Duration,
+    sent: VecDeque<Instant>,
+}
+
+pub enum NetworkPolicyDenial {
+    Host,
+    RateLimit,
+}
+
+impl NetworkPolicy {
+    pub fn new(config_plugin_network: Option<ConfigPluginNetwork>) -> Self {
+        let Some(config_plugin_network) = config_plugin_network else {
+            return NetworkPolicy {
+                allow: None,
+                rate_limit: None,
+            };
+        };
+
+        NetworkPolicy {
+            allow: config_plugin_network
+                .allow
+                .map(|allow| allow.into_iter().map(|host| host.to_lowercase()).collect()),
+            rate_limit: config_plugin_network
+                .rate_limit
+                .map(|rate_limit| NetworkPolicyRateLimit {
+                    requests: rate_limit.requests.get(),
+                    window: Duration::from_secs(rate_limit.seconds.get()),
+                    sent: VecDeque::with_capacity(rate_limit.requests.get()),
+                }),
+        }
+    }
+
+    pub fn check(&mut self, host: Option<&str>) -> Result<(), NetworkPolicyDenial> {
+        if let Some(allow) = &self.allow {
+            let Some(host) = host else {
+                return Err(NetworkPolicyDenial::Host);
+            };
+
+            let host = host.to_lowercase();
+
+            if !allow
+                .iter()
+                .any(|pattern| Self::host_matches(pattern, &host))
+            {
+                return Err(NetworkPolicyDenial::Host);
+            }
+        }
+
+        if let Some(rate_limit) = &mut self.rate_limit
+            && !rate_limit.allows(Instant::now())
+        {
+            return Err(NetworkPolicyDenial::RateLimit);
+        }
+
+        Ok(())
+    }
+
+    fn host_matches(pattern: &str, host: &str) -> bool {
+        match pattern.strip_prefix("*.") {
+            Some(domain) => host
+                .strip_suffix(domain)
+                .is_some_and(|subdomain| subdomain.len() > 1 && subdomain.ends_with('.')),
+            None => pattern == host,
+        }
+    }
+}
+
+impl NetworkPolicyRateLimit {
+    /// Records the request when it fits in the window, requests older than the window expire.
+    fn allows(&mut self, now: Instant) -> bool {
+        while let Some(sent) = self.sent.front()
+            && now.duration_since(*sent) >= self.window
+        {
+            self.sent.pop_front();
+        }
+
+        if self.sent.len() >= self.requests {
+            return false;
+        }
+
+        self.sent.push_back(now);
+
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn host_matches_wildcard_and_bare_domains() {
+        assert!(NetworkPolicy::host_matches(
+            "*.example.com",
+            "api.example.com"
+        ));
+        assert!(NetworkPolicy::host_matches(
+            "*.example.com",
+            "a.b.example.com"
+        ));
+        assert!(!NetworkPolicy::host_matches("*.example.com", "example.com"));
+        assert!(!NetworkPolicy::host_matches(
+            "*.example.com",
+            "badexample.com"
+        ));
+        assert!(!NetworkPolicy::host_matches(
+            "*.example.com",
+            ".example.com"
+        ));
+        assert!(NetworkPolicy::host_matches("example.com", "example.com"));
+        assert!(!NetworkPolicy::host_matches(
+            "example.com",
+            "api.example.com"
+        ));
+    }
+
+    #[test]
+    fn check_ignores_host_case() {
+        let mut network_policy = NetworkPolicy::new(Some(ConfigPluginNetwork {
+            allow: Some(vec![
+                String::from("*.Example.COM"),
+                String::from("Api.Test.org"),
+            ]),
+            rate_limit: None,
+        }));
+
+        assert!(network_policy.check(Some("WWW.example.com")).is_ok());
+        assert!(network_policy.check(Some("api.test.ORG")).is_ok());
+        assert!(matches!(
+            network_policy.check(Some("Example.com")),
+            Err(NetworkPolicyDenial::Host)
+        ));
+        assert!(matches!(
+            network_policy.check(None),
+            Err(NetworkPolicyDenial::Host)
+        ));
+    }
+
+    #[test]
+    fn rate_limit_rejects_zero() {
+        for rate_limit in [
+            "rate_limit: {requests: 0, seconds: 60}",
+            "rate_limit: {requests: 10, seconds: 0}",
+        ] {
+            assert!(serde_yaml_ng::from_str::<ConfigPluginNetwork>(rate_limit).is_err());
+        }
+
+        assert!(
+            serde_yaml_ng::from_str::<ConfigPluginNetwork>(
+                "rate_limit: {requests: 10, seconds: 60}"
+            )
+            .is_ok()
+        );
+    }
+
+    #[test]
+    fn rate_limit_window_expires() {
+        let mut rate_limit = NetworkPolicyRateLimit {
+            requests: 2,
+            window: Duration::from_secs(10),
+            sent: VecDeque::new(),
+        };
+
+        let now = Instant::now();
+
+        assert!(rate_limit.allows(now));
+        assert!(rate_limit.allows(now + Duration::from_secs(1)));
+        assert!(!rate_limit.allows(now + Duration::from_secs(9)));
+
+        // Only the first request expired
+        assert!(rate_limit.allows(now + Duration::from_secs(10)));
+        assert!(!rate_limit.allows(now + Duration::from_secs(10)));
+
+        assert!(rate_limit.allows(now + Duration::from_secs(21)));
+        assert!(rate_limit.allows(now + Duration::from_secs(21)));
+    }
+}
@@ -0,0 +1,125 @@
+/* SPDX-License-Identifier: GPL-3.0-or-later */
+/* Copyright © 2026 Eduard Smet */
+
+use std::{fs, io::ErrorKind, path::Path};
+
+use jsonschema::paths::LocationSegment;
+use serde::Deserialize;
+use serde_json::Value as JsonValue;
+use serde_yaml_ng::Value;
+use tracing::{debug, error};
+
+#[derive(Deserialize)]
+struct PluginMetadata {
+    settings_schema: Option<JsonValue>,
+}
+
+/// Validates the settings of a plugin against the JSON Schema from its `metadata.json` file,
+/// default values from the schema are filled in before validating.
+pub fn validate(
+    plugin_uid: &str,
+    plugin_directory: &Path,
+    settings: Option<Value>,
+) -> Result<JsonValue, ()> {
+    let mut settings = match serde_json::to_value(settings.unwrap_or_default()) {
+        Ok(settings) => settings,
+        Err(err) => {
+            error!("The settings of the {plugin_uid} plugin could not be converted to JSON: {err}");
+            return Err(());
+        }
+    };
+
+    let metadata_bytes = match fs::read(plugin_directory.join("metadata.json")) {
+        Ok(metadata_bytes) => metadata_bytes,
+        Err(err) => {
+            if err.kind() == ErrorKind::NotFound {
+                debug!(
+                    "The {plugin_uid} plugin has no metadata file, skipping settings validation"
+                );
+                return Ok(settings);
+            }
+
+            error!(
+                "An error occurred while reading the metadata file of the {plugin_uid} plugin: {err}"
+            );
+            return Err(());
+        }
+    };
+
+    let Some(settings_schema) = (match serde_json::from_slice::<PluginMetadata>(&metadata_bytes) {
+        Ok(metadata) => metadata.settings_schema,
+        Err(err) => {
+            error!(
+                "An error occurred while deserializing the metadata file of the {plugin_uid} plugin: {err}"
+            );
+            return Err(());
+        }
+    }) else {
+        return Ok(settings);
+    };
+
+    let validator = match jsonschema::validator_for(&settings_schema) {
+        Ok(validator) => validator,
+        Err(err) => {
+            error!("The settings schema of the {plugin_uid} plugin is invalid: {err}");
+            return Err(());
+        }
+    };
+
+    if settings.is_null() {
+        if let Some(default) = settings_schema.get("default") {
+            settings = default.clone();
+        } else if settings_schema.get("type").and_then(JsonValue::as_str) == Some("object") {
+            settings = JsonValue::Object(serde_json::Map::new());
+        }
+    }
+
+    apply_defaults(&settings_schema, &mut settings);
+
+    let mut valid = true;
+
+    for err in validator.iter_errors(&settings) {
+        let mut path = format!("plugins.{plugin_uid}.settings");
+
+        for segment in err.instance_path() {
+            match segment {
+                LocationSegment::Property(property) => {
+                    path.push('.');
+                    path.push_str(&property);
+                }
+                LocationSegment::Index(index) => path.push_str(&format!("[{index}]")),
+            }
+        }
+
+        error!("The config file is invalid at {path}: {err}");
+
+        valid = false;
+    }
+
+    if !valid {
+        return Err(());
+    }
+
+    Ok(settings)
+}
+
+fn apply_defaults(schema: &JsonValue, instance: &mut JsonValue) {
+    let (Some(properties), Some(instance)) = (
+        schema.get("properties").and_then(JsonValue::as_object),
+        instance.as_object_mut(),
+    ) else {
+        return;
+    };
+
+    for (key, property_schema) in properties {
+        if !instance.contains_key(key)
+            && let Some(default) = property_schema.get("default")
+        {
+            instance.insert(key.clone(), default.clone());
+        }
+
+        if let Some(value) = instance.get_mut(key) {
+            apply_defaults(property_schema, value);
+        }
+    }
+}
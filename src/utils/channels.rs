@@ -6,17 +6,29 @@ use tokio::sync::{
     oneshot::Sender as OSSender,
 };
 
-use crate::plugins::{
-    PluginRegistrationRequestsApplicationCommand, PluginRegistrationRequestsScheduledJob,
-    discord_bot::plugin::host_functions::{DiscordRequests, DiscordResponses},
-    exports::discord_bot::plugin::plugin_functions::DiscordEvents,
+use crate::{
+    discord::cache::DiscordCacheRequests,
+    plugins::{
+        PluginRegistrationRequestsScheduledJob,
+        commands::PluginApplicationCommand,
+        discord_bot::plugin::host_functions::{
+            DiscordRequestError, DiscordRequests, DiscordResponses,
+        },
+        exports::discord_bot::plugin::plugin_functions::DiscordEvent,
+    },
 };
 
 pub enum DiscordBotClientMessages {
-    RegisterApplicationCommands(Vec<PluginRegistrationRequestsApplicationCommand>),
+    RegisterApplicationCommands(Vec<PluginApplicationCommand>),
     Request(
+        String,
         DiscordRequests,
-        OSSender<Result<Option<DiscordResponses>, String>>,
+        OSSender<Result<Option<DiscordResponses>, DiscordRequestError>>,
+    ),
+    CacheRequest(
+        String,
+        DiscordCacheRequests,
+        OSSender<Result<Option<Vec<u8>>, String>>,
     ),
     Shutdown(OSSender<()>),
 }
@@ -27,7 +39,7 @@ pub enum JobSchedulerMessages {
 }
 
 pub enum RuntimeMessages {
-    CallDiscordEvent(String, DiscordEvents),
+    CallDiscordEvent(String, DiscordEvent),
     CallScheduledJob(String, String),
 }
 